@@ -1,9 +1,65 @@
-use crate::error::{ApiError, Result};
-use crate::lights::Light;
-use reqwest::blocking::get;
+use crate::color::{self, Color};
+use crate::discovery::BridgeFinder;
+use crate::events::EventStream;
+use crate::config::{BridgeConfig, Capabilities, SoftwareUpdateState, WhitelistEntry, ZIGBEE_CHANNELS};
+use crate::error::{ApiError, Error, Result};
+use crate::groups::{Group, RoomClass};
+use crate::resourcelinks::ResourceLink;
+use crate::rules::Rule;
+use crate::scenes::Scene;
+use crate::schedules::{Command, Schedule};
+use crate::sensors::{Sensor, SensorConfigChange};
+use crate::transport::{Method, Transport};
+#[cfg(feature = "v2")]
+use crate::v2::{self, EntertainmentArea, GradientPoint, LightV2};
+#[cfg(feature = "entertainment")]
+use crate::entertainment::StreamSession;
+use crate::rate_limit::RateLimiter;
+use crate::lights::{Alert, Effect, Light, LightHandle, LightState, LightStateChange, NewLights, StartupBehavior, UnparseableLights};
+use reqwest::blocking::Client;
 use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use xml::escape::escape_str_pcdata;
+use xml::reader::{EventReader, XmlEvent};
 
-#[derive(Deserialize, Serialize, Debug)]
+/// Application name used by `register_user`
+const DEFAULT_APP_NAME: &str = "hust";
+
+/// Device name used by `register_user`
+const DEFAULT_DEVICE_NAME: &str = "Hust Hue API client";
+
+/// User agent sent with all requests by default
+pub(crate) const DEFAULT_USER_AGENT: &str = concat!("hust/", env!("CARGO_PKG_VERSION"));
+
+/// Header which authenticates requests to the API v2 and the event stream
+const APPLICATION_KEY_HEADER: &str = "hue-application-key";
+
+/// Number of requests `switch_lights` sends at the same time
+const MAX_PARALLEL_REQUESTS: usize = 4;
+
+/// Interval in which `wait_for_state` reads the state of a light
+const STATE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Namespace of the elements of a UPnP device description
+const UPNP_DEVICE_NAMESPACE: &str = "urn:schemas-upnp-org:device-1-0";
+
+/// Attributes of a light state which are set to absolute values, so setting them twice does no harm
+const REPEATABLE_STATE_KEYS: &[&str] = &["on", "bri", "hue", "sat", "xy", "ct", "effect", "transitiontime"];
+
+/// Delay before the first retry of a failed request, doubled for each further retry
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Longest delay before retrying a request
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 /// Core defice infoormation about a bridge
 pub struct BridgeDevice {
     #[serde(rename = "UDN")]
@@ -26,13 +82,82 @@ pub struct BridgeDevice {
 /// The struct attributes contain the static properties of the bridge.
 /// 
 /// The methods can be used for communication like commands.
-#[derive(Deserialize, Serialize, Debug)]
+/// 
+/// Cloning is cheap: Clones share the transport and the rate limits, as well
+/// as the HTTP connections if a request was sent before cloning. So e.g.
+/// each worker thread can get its own clone.
+/// 
+/// A bridge is `Send` and `Sync`, so it can also be shared between threads,
+/// e.g. in an `Arc`, and used from several of them at the same time:
+/// ```no_run
+/// use hust::bridge::Bridge;
+/// use std::sync::Arc;
+/// 
+/// # fn main() -> hust::Result<()> {
+/// let bridge = Arc::new(Bridge::from_ip("192.168.1.5")?);
+/// let threads: Vec<_> = ["1", "2"]
+///     .iter()
+///     .map(|light| {
+///         let bridge = bridge.clone();
+///         std::thread::spawn(move || bridge.switch_light("user", light, true))
+///     })
+///     .collect();
+/// for thread in threads {
+///     thread.join().unwrap()?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Bridge {
     #[serde(rename = "URLBase")]
     /// The base URL of the bridge, which all `/api/` resources are below of.
     pub url_base: String,
     /// The device properties of this bridge.
     pub device: BridgeDevice,
+    /// The HTTP client, reused by all requests to keep connections alive.
+    ///
+    /// It is created on first use, since the blocking client must not be
+    /// constructed inside an async runtime.
+    #[serde(skip)]
+    client: OnceLock<Client>,
+    /// The bridge id, formed from the serial number on first use
+    #[serde(skip)]
+    id: OnceLock<String>,
+    /// Timeout for the requests of the blocking client
+    #[serde(skip)]
+    timeout: Option<Duration>,
+    /// Tells wether invalid TLS certificates are accepted
+    #[serde(skip)]
+    accept_invalid_certs: bool,
+    /// The user agent of all requests, if not the default one
+    #[serde(skip)]
+    user_agent: Option<String>,
+    /// A custom transport, used instead of the HTTP client if set
+    #[serde(skip)]
+    transport: Option<Arc<dyn Transport>>,
+    /// How often to retry requests after transient failures
+    #[serde(skip)]
+    max_retries: u32,
+    /// Throttles the changes of light states, if set
+    #[serde(skip)]
+    light_rate_limiter: Option<Arc<RateLimiter>>,
+    /// Throttles the changes of group states, if set
+    #[serde(skip)]
+    group_rate_limiter: Option<Arc<RateLimiter>>,
+    /// The HTTP client used by the `*_async` methods.
+    #[cfg(feature = "async")]
+    #[serde(skip, default = "default_async_client")]
+    async_client: reqwest::Client,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+/// The credentials of a registered user
+pub struct Credentials {
+    /// The name of the user, needed for all requests
+    pub username: String,
+    /// The key for the Entertainment API, if it was requested
+    pub clientkey: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -46,32 +171,478 @@ pub enum ApiResponseSection {
     Success(HashMap<String, serde_json::Value>),
 }
 
+#[derive(Deserialize, Debug)]
+/// Basic element of a response to a deletion
+/// 
+/// Unlike other requests, the success of a deletion is reported as a
+/// plain string like `"/lights/1 deleted"`.
+pub enum DeletionResponseSection {
+    #[serde(rename = "error")]
+    Err(ApiError),
+    #[serde(rename = "success")]
+    Success(String),
+}
+
 impl Bridge {
     /// Creates a Bridge object from a description URL like returned in SSDP discovery.
     pub fn from_description_url(url: String) -> Result<Bridge> {
-        let response = get(&url)?.text()?;
-        let bridge: Bridge = serde_xml_rs::from_str(&response)?;
+        let client = Client::builder().user_agent(DEFAULT_USER_AGENT).build()?;
+        let response = Transport::get(&client, &url)?;
+        let bridge = Bridge::from_description(&String::from_utf8_lossy(&response))?;
+        Ok(bridge.with_client(client))
+    }
+
+    /// Like `from_description_url`, but fails if the description isn't fetched within `timeout`.
+    /// 
+    /// The timeout only applies to fetching the description, not to the
+    /// requests of the bridge.
+    pub(crate) fn from_description_url_within(url: &str, timeout: Duration) -> Result<Bridge> {
+        let client = Client::builder().user_agent(DEFAULT_USER_AGENT).timeout(timeout).build()?;
+        let response = Transport::get(&client, url)?;
+        Bridge::from_description(&String::from_utf8_lossy(&response))
+    }
+
+    /// Creates a Bridge object from the content of a `description.xml`.
+    /// 
+    /// The base URL gets a trailing slash if it lacks one, since all
+    /// resources are appended to it:
+    /// ```
+    /// use hust::bridge::Bridge;
+    /// 
+    /// let description = |url_base| format!(
+    ///     "<root><URLBase>{}</URLBase><device><UDN>uuid:2f402f80-da50-11e1-9b23-001788255acc</UDN>\
+    ///     <deviceType>urn:schemas-upnp-org:device:Basic:1</deviceType><manufacturer>Signify</manufacturer>\
+    ///     <modelName>Philips hue bridge 2015</modelName><modelDescription>Philips hue Personal Wireless Lighting</modelDescription>\
+    ///     <serialNumber>001788255acc</serialNumber><friendlyName>Hue Bridge (192.168.1.5)</friendlyName></device></root>",
+    ///     url_base,
+    /// );
+    /// for url_base in &["http://192.168.1.5:80/", "http://192.168.1.5:80"] {
+    ///     let bridge = Bridge::from_description(&description(url_base)).unwrap();
+    ///     assert_eq!(bridge.url_base, "http://192.168.1.5:80/");
+    /// }
+    /// 
+    /// // Leading whitespace and elements of other namespaces are ignored
+    /// let extended = description(&"http://192.168.1.5:80/").replacen(
+    ///     "<root>",
+    ///     "\n<root xmlns=\"urn:schemas-upnp-org:device-1-0\" xmlns:x=\"urn:vendor\"><x:URLBase>http://other/</x:URLBase>",
+    ///     1,
+    /// );
+    /// assert_eq!(Bridge::from_description(&extended).unwrap().url_base, "http://192.168.1.5:80/");
+    /// ```
+    pub fn from_description(xml: &str) -> Result<Bridge> {
+        let xml = normalize_description(xml).map_err(|source| serde_xml_rs::Error::Syntax { source })?;
+        let mut bridge: Bridge = serde_xml_rs::from_str(&xml)?;
+        if !bridge.url_base.ends_with('/') {
+            bridge.url_base.push('/');
+        }
         Ok(bridge)
     }
 
+    /// Creates a Bridge object from the IP address of the bridge.
+    /// 
+    /// `ip` may be a bare address like `192.168.1.5` or include a port
+    /// like `192.168.1.5:8080`.
+    pub fn from_ip(ip: &str) -> Result<Bridge> {
+        Bridge::from_description_url(format!("http://{}/description.xml", ip))
+    }
+
+    /// Creates a Bridge object from its base URL and device properties.
+    pub fn new(url_base: String, device: BridgeDevice) -> Bridge {
+        Bridge {
+            url_base,
+            device,
+            client: OnceLock::new(),
+            id: OnceLock::new(),
+            timeout: None,
+            accept_invalid_certs: false,
+            user_agent: None,
+            transport: None,
+            max_retries: 0,
+            light_rate_limiter: None,
+            group_rate_limiter: None,
+            #[cfg(feature = "async")]
+            async_client: default_async_client(),
+        }
+    }
+
+    /// Use the given HTTP client for all blocking requests.
+    /// 
+    /// This allows to configure proxies, root certificates or the user
+    /// agent, or to share a connection pool between bridges. Note that
+    /// `with_timeout`, `accept_self_signed` and `with_user_agent` build a
+    /// new client and thereby replace the given one.
+    pub fn with_client(mut self, client: Client) -> Bridge {
+        self.client = OnceLock::from(client);
+        self.transport = None;
+        self
+    }
+
+    /// Send all blocking API requests through the given transport.
+    /// 
+    /// This replaces the HTTP client, so settings like `with_timeout`
+    /// don't have any effect while a custom transport is used. It is
+    /// useful to test code using a bridge without having one:
+    /// ```
+    /// use hust::bridge::{Bridge, BridgeDevice};
+    /// use hust::transport::{Method, Transport};
+    /// use std::sync::{Arc, Mutex};
+    /// 
+    /// /// Remembers the URLs and pretends each change succeeded
+    /// #[derive(Debug, Default)]
+    /// struct FakeBridge {
+    ///     urls: Mutex<Vec<String>>,
+    /// }
+    /// 
+    /// impl Transport for FakeBridge {
+    ///     fn request(&self, _: Method, url: &str, _: Option<Vec<u8>>) -> hust::Result<Vec<u8>> {
+    ///         self.urls.lock().unwrap().push(String::from(url));
+    ///         Ok(br#"[{"success": {"/lights/1/state/on": true}}]"#.to_vec())
+    ///     }
+    /// }
+    /// 
+    /// let fake = Arc::new(FakeBridge::default());
+    /// let bridge = Bridge::new(String::from("http://bridge/"), BridgeDevice::default())
+    ///     .with_transport(fake.clone());
+    /// bridge.switch_light("user", "1", true).unwrap();
+    /// assert_eq!(fake.urls.lock().unwrap()[0], "http://bridge/api/user/lights/1/state");
+    /// ```
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Bridge {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Send all requests to `base` instead of the `URLBase` of the description.
+    /// 
+    /// This allows to reach a bridge through a reverse proxy or a tunnel,
+    /// e.g. `http://localhost:8080/`. Since all resources are below of the
+    /// base URL, it has to end with a slash:
+    /// ```
+    /// use hust::bridge::{Bridge, BridgeDevice};
+    /// 
+    /// let bridge = Bridge::new(String::from("http://192.168.1.5:80/"), BridgeDevice::default());
+    /// assert!(bridge.clone().with_base_url(String::from("http://localhost:8080")).is_err());
+    /// let bridge = bridge.with_base_url(String::from("http://localhost:8080/")).unwrap();
+    /// assert_eq!(bridge.url_base, "http://localhost:8080/");
+    /// ```
+    pub fn with_base_url(mut self, base: String) -> Result<Bridge> {
+        if !base.ends_with('/') {
+            return Err(Error::Arbitrary(format!("The base URL {} must end with a slash", base)));
+        }
+        self.url_base = base;
+        Ok(self)
+    }
+
+    /// The transport for blocking requests.
+    fn transport(&self) -> &dyn Transport {
+        match &self.transport {
+            Some(transport) => transport.as_ref(),
+            None => self.client(),
+        }
+    }
+
+    /// Retry requests up to `max_retries` times after transient failures.
+    /// 
+    /// The bridge sometimes answers with `503 Service Unavailable` or
+    /// drops requests when it's busy, e.g. when too many requests are sent
+    /// in a short time. Such requests are retried after a delay, which
+    /// starts at 250ms and doubles with each retry, up to 8s.
+    /// 
+    /// Only requests which can safely be sent twice are retried, since the
+    /// first attempt may have taken effect despite the failure. Each
+    /// method tells wether its request is one of them:
+    /// * Reading anything, like with `get_all_lights`, is retried.
+    /// * Setting attributes to absolute values, like with `switch_light`,
+    ///   `set_light_name` or `modify_light_state`, is retried.
+    /// * Changing attributes relatively, like with `adjust_brightness`, is
+    ///   never retried, since the changes would add up.
+    /// * Actions, like alerts, `activate_scene`, `touchlink` or
+    ///   `start_software_update`, are never retried, since they would be
+    ///   carried out again. This includes changes of a light or group state
+    ///   with an alert or a scene.
+    /// * Creating resources, like with `create_group`, `create_room`,
+    ///   `create_schedule` or `register_user`, is never retried, since
+    ///   each attempt would create another one.
+    /// * Deleting resources is never retried: If only the answer got lost,
+    ///   the retry would fail with `ResourceNotAvailable`, although the
+    ///   resource was deleted.
+    /// * With `request` and the API v2, only GET requests are retried.
+    /// ```no_run
+    /// use hust::bridge::Bridge;
+    /// 
+    /// # fn main() -> hust::Result<()> {
+    /// let bridge = Bridge::from_ip("192.168.1.5")?.with_retry(3);
+    /// bridge.switch_light("user", "1", true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_retry(mut self, max_retries: u32) -> Bridge {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Limit how many light and group states are changed per second.
+    /// 
+    /// The bridge drops commands if it gets too many of them, which happens
+    /// easily when changing many lights in a loop. Philips recommends at
+    /// most 10 light changes and 1 group change per second. With a rate
+    /// limit, requests hold back until they are within the limit, so
+    /// they are spaced evenly instead of being sent in bursts.
+    /// 
+    /// A limit of 0 disables the rate limit.
+    pub fn with_rate_limit(mut self, lights_per_sec: u32, groups_per_sec: u32) -> Bridge {
+        self.light_rate_limiter = RateLimiter::new(lights_per_sec).map(Arc::new);
+        self.group_rate_limiter = RateLimiter::new(groups_per_sec).map(Arc::new);
+        self
+    }
+
+    /// The rate limiter for a request, if the request changes a light or group state.
+    fn rate_limiter(&self, method: &Method, url: &str) -> Option<&RateLimiter> {
+        if *method != Method::PUT {
+            return None;
+        }
+        if url.contains("/lights/") && url.ends_with("/state") || url.contains("/resource/light/") {
+            self.light_rate_limiter.as_deref()
+        } else if url.contains("/groups/") && url.ends_with("/action") || url.contains("/resource/grouped_light/") {
+            self.group_rate_limiter.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Sends a request via the transport, retrying it after transient failures if allowed.
+    /// 
+    /// With the `log` feature, each attempt is logged at debug level with
+    /// its outcome and duration.
+    /// 
+    /// `retryable` tells wether the request would have the same effect if
+    /// it was received twice, see `with_retry`.
+    fn send(&self, method: Method, url: &str, body: Option<Vec<u8>>, retryable: bool) -> Result<Vec<u8>> {
+        self.send_with_headers(method, url, &[], body, retryable)
+    }
+
+    /// Like `send`, with additional headers.
+    fn send_with_headers(&self, method: Method, url: &str, headers: &[(&str, &str)], body: Option<Vec<u8>>, retryable: bool) -> Result<Vec<u8>> {
+        self.attempt(&method, url, retryable, || {
+            self.transport().request_with_headers(method.clone(), url, headers, body.clone())
+        })
+    }
+
+    /// Makes attempts at a request until it succeeds or mustn't be retried.
+    /// 
+    /// Each attempt waits for the rate limit and is logged.
+    fn attempt<T: ResponseBody>(&self, method: &Method, url: &str, retryable: bool, mut request: impl FnMut() -> Result<T>) -> Result<T> {
+        let mut retries = 0;
+        loop {
+            if let Some(rate_limiter) = self.rate_limiter(method, url) {
+                sleep(rate_limiter.reserve());
+            }
+            #[cfg(feature = "log")]
+            let started = Instant::now();
+            let response = request();
+            #[cfg(feature = "log")]
+            log_request(method, url, &response, started.elapsed());
+            match response {
+                Err(e) if retryable && retries < self.max_retries && is_transient(&e) => {
+                    sleep(RETRY_DELAY.saturating_mul(2u32.saturating_pow(retries)).min(MAX_RETRY_DELAY));
+                    retries += 1;
+                }
+                response => return response,
+            }
+        }
+    }
+
+    /// Sets a timeout for requests to the bridge.
+    /// 
+    /// The timeout applies to connecting as well as to each request as a
+    /// whole, so a bridge which doesn't answer results in an error in time:
+    /// ```
+    /// use hust::bridge::{Bridge, BridgeDevice};
+    /// use std::net::TcpListener;
+    /// use std::time::{Duration, Instant};
+    /// 
+    /// // Accepts connections, but never answers
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let url = format!("http://{}/", listener.local_addr().unwrap());
+    /// let bridge = Bridge::new(url, BridgeDevice::default())
+    ///     .with_timeout(Duration::from_millis(500));
+    /// let start = Instant::now();
+    /// assert!(bridge.get_all_lights("user").is_err());
+    /// assert!(start.elapsed() < Duration::from_secs(2));
+    /// ```
+    pub fn with_timeout(mut self, timeout: Duration) -> Bridge {
+        self.timeout = Some(timeout);
+        self.client = OnceLock::new();
+        self
+    }
+
+    /// Accept the self-signed certificate of the bridge for HTTPS.
+    /// 
+    /// Bridges serve HTTPS with a certificate signed by Philips' own CA for
+    /// the bridge id, which isn't trusted by default. Note that accepting it
+    /// also accepts any other invalid certificate, so only do this in a
+    /// network you trust.
+    /// 
+    /// Whether HTTP or HTTPS is used depends on the scheme of `url_base`.
+    pub fn accept_self_signed(mut self, yes: bool) -> Bridge {
+        self.accept_invalid_certs = yes;
+        self.client = OnceLock::new();
+        #[cfg(feature = "async")]
+        {
+            self.async_client = self.build_async_client();
+        }
+        self
+    }
+
+    /// Send `user_agent` as the `User-Agent` of all requests.
+    /// 
+    /// By default, the bridge is told the name and version of this crate,
+    /// like `hust/0.2.1`. Like `with_timeout`, this builds a new client.
+    /// ```
+    /// use hust::bridge::{Bridge, BridgeDevice};
+    /// 
+    /// let bridge = Bridge::new(String::from("http://192.168.1.5:80/"), BridgeDevice::default());
+    /// assert!(bridge.user_agent().starts_with("hust/"));
+    /// let bridge = bridge.with_user_agent("my-dashboard/1.0");
+    /// assert_eq!(bridge.user_agent(), "my-dashboard/1.0");
+    /// ```
+    pub fn with_user_agent(mut self, user_agent: &str) -> Bridge {
+        self.user_agent = Some(String::from(user_agent));
+        self.client = OnceLock::new();
+        #[cfg(feature = "async")]
+        {
+            self.async_client = self.build_async_client();
+        }
+        self
+    }
+
+    /// The user agent of all requests.
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+
+    /// Talk to the bridge via HTTPS instead of HTTP.
+    /// 
+    /// This changes the scheme of `url_base` to `https` and drops the
+    /// port 80 the bridge announces for HTTP. Newer bridges require HTTPS,
+    /// usually together with `accept_self_signed`.
+    /// ```
+    /// use hust::bridge::{Bridge, BridgeDevice};
+    /// 
+    /// let bridge = Bridge::new(String::from("http://192.168.1.5:80/"), BridgeDevice::default());
+    /// assert_eq!(bridge.use_https().url_base, "https://192.168.1.5/");
+    /// ```
+    pub fn use_https(mut self) -> Bridge {
+        self.url_base = https_url(&self.url_base);
+        self
+    }
+
+    /// The HTTP client for blocking requests.
+    /// 
+    /// Panics like `Client::new` if the client can't be built.
+    fn client(&self) -> &Client {
+        self.client.get_or_init(|| {
+            let mut builder = Client::builder();
+            if let Some(timeout) = self.timeout {
+                builder = builder.timeout(timeout).connect_timeout(timeout);
+            }
+            builder = builder
+                .danger_accept_invalid_certs(self.accept_invalid_certs)
+                .user_agent(self.user_agent());
+            builder.build().expect("Failed to build HTTP client")
+        })
+    }
+
     /// The unique but user-friendly name of the bridge.
     pub fn user_readable_identifier(&self) -> &str {
         &self.device.friendly_name
     }
 
+    /// The serial number of the bridge, which is also its MAC address.
+    pub fn serial_number(&self) -> &str {
+        &self.device.serial_number
+    }
+
+    /// The unique identifier of the bridge, like `001788FFFE255ACC`.
+    /// 
+    /// This is the id which bridges announce when they're discovered, so
+    /// it matches [`crate::DiscoveredBridge::id`]. It is formed from the
+    /// serial number by inserting `FFFE` in the middle. Unlike the
+    /// friendly name, it can serve as a key for several bridges:
+    /// ```
+    /// use hust::bridge::{Bridge, BridgeDevice};
+    /// 
+    /// let device = BridgeDevice { serial_number: String::from("001788255acc"), ..BridgeDevice::default() };
+    /// let bridge = Bridge::new(String::from("http://192.168.1.5:80/"), device);
+    /// assert_eq!(bridge.id(), "001788FFFE255ACC");
+    /// ```
+    pub fn id(&self) -> &str {
+        self.id.get_or_init(|| bridge_id_of(&self.device.serial_number))
+    }
+
+    /// The model name of the bridge, e.g. `Philips hue bridge 2015`.
+    pub fn model_name(&self) -> &str {
+        &self.device.model_name
+    }
+
     /// Registers a user and return its name.
     /// 
     /// Save it to communicate further with the bridge, e.g. to switch lights.
     /// 
-    /// Note that the button of the bridge has to be pressed.
+    /// Note that the button of the bridge has to be pressed. Otherwise, the
+    /// returned error tells so via [`Error::is_link_button_not_pressed`],
+    /// and the registration can be tried again after the button is pressed:
+    /// ```no_run
+    /// use hust::Bridge;
+    /// 
+    /// fn register(bridge: &Bridge) -> hust::Result<String> {
+    ///     match bridge.register_user() {
+    ///         Err(e) if e.is_link_button_not_pressed() => {
+    ///             println!("Please press the button of the bridge");
+    ///             std::thread::sleep(std::time::Duration::from_secs(5));
+    ///             bridge.register_user()
+    ///         }
+    ///         result => result,
+    ///     }
+    /// }
+    /// ```
     pub fn register_user(&self) -> Result<String> {
-        let client = reqwest::blocking::Client::new();
+        self.register_user_named(DEFAULT_APP_NAME, DEFAULT_DEVICE_NAME)
+    }
+
+    /// Registers a user for an application on a device and return its name.
+    /// 
+    /// The bridge lists the user as `app_name#device_name`, so that the
+    /// applications using it can be told apart, e.g. in the Hue app.
+    /// 
+    /// Note that the button of the bridge has to be pressed.
+    pub fn register_user_named(&self, app_name: &str, device_name: &str) -> Result<String> {
 		let mut url = self.url_base.clone();
 		url.push_str("api");
+        let devicetype = format!("{}#{}", app_name, device_name);
         let mut params = HashMap::new();
-        params.insert("devicetype", "Hust Hue API client");
-        let response = client.post(&url).json(&params).send()?;
-        let response: Vec<ApiResponseSection> = serde_json::from_reader(response)?;
+        params.insert("devicetype", devicetype.as_str());
+        let response = self.post(&url, &params)?;
+        Ok(self.registration_result(response)?.username)
+    }
+
+    /// Registers a user and let the bridge generate a client key for it.
+    /// 
+    /// The client key is needed for the Entertainment API and can't be
+    /// retrieved later on, so save it together with the user name.
+    /// 
+    /// Note that the button of the bridge has to be pressed.
+    pub fn register_user_with_clientkey(&self) -> Result<Credentials> {
+        let url = format!("{}api", self.url_base);
+        let body = serde_json::json!({
+            "devicetype": format!("{}#{}", DEFAULT_APP_NAME, DEFAULT_DEVICE_NAME),
+            "generateclientkey": true,
+        });
+        let response = self.post(&url, &body)?;
+        self.registration_result(response)
+    }
+
+    /// Analyzes the response to a user registration request
+    fn registration_result(&self, response: Vec<ApiResponseSection>) -> Result<Credentials> {
         // Now, analyze the response to measure success or failure.
         let mut errors = vec![];
         let mut success = None;
@@ -82,8 +653,14 @@ impl Bridge {
             }
         }
         if let Some(hashmap) = success {
-            if let Some(username) = hashmap.get("username") {
-                return Ok(username.to_string());
+            if let Some(username) = hashmap.get("username").and_then(|name| name.as_str()) {
+                return Ok(Credentials {
+                    username: String::from(username),
+                    clientkey: hashmap
+                        .get("clientkey")
+                        .and_then(|key| key.as_str())
+                        .map(String::from),
+                });
             }
         }
 		Err(errors)?
@@ -111,6 +688,52 @@ impl Bridge {
         Err(errors)?
     }
 
+    /// Analyzes the response to a request that changes something, keeping the success payload
+    /// 
+    /// The success sections of the response are merged into one map from
+    /// the address of each changed attribute to the value the bridge applied.
+    fn light_change_values(&self, response: Vec<ApiResponseSection>) -> Result<HashMap<String, serde_json::Value>> {
+        let mut errors = vec![];
+        let mut values = HashMap::new();
+        let mut success = false;
+        for section in response {
+            match section {
+                ApiResponseSection::Success(section_values) => {
+                    success = true;
+                    values.extend(section_values);
+                }
+                ApiResponseSection::Err(e) => errors.push(e),
+            }
+        }
+        if success {
+            return Ok(values)
+        }
+        Err(errors)?
+    }
+
+    /// Analyzes the response to a request that reads a resource
+    /// 
+    /// The bridge answers with the resource itself on success, but with
+    /// a list of error sections on failure, e.g. for an unknown id.
+    fn resource_result<T: serde::de::DeserializeOwned>(&self, response: serde_json::Value) -> Result<T> {
+        // Some resources are lists themselves, like the time zones
+        let is_error_list = response
+            .as_array()
+            .is_some_and(|sections| !sections.is_empty() && sections.iter().all(|section| section.get("error").is_some()));
+        if is_error_list {
+            let sections: Vec<ApiResponseSection> = serde_json::from_value(response)?;
+            let errors: Vec<ApiError> = sections
+                .into_iter()
+                .filter_map(|section| match section {
+                    ApiResponseSection::Err(e) => Some(e),
+                    ApiResponseSection::Success(_) => None,
+                })
+                .collect();
+            return Err(errors.into());
+        }
+        Ok(serde_json::from_value(response)?)
+    }
+
     /// Set an attribute of a light.
     /// 
     /// `user` is the user you had to register with `register_user`.
@@ -120,15 +743,57 @@ impl Bridge {
     /// 
     /// `key` can be any attribute of [`crate::lights::LightState`].
     pub fn modify_light<T: serde::ser::Serialize>(&self, user: &str, light: &str, key: &str, value: T) -> Result<()> {
-        let client = reqwest::blocking::Client::new();
+        let mut params = HashMap::new();
+        params.insert(key, value);
+        self.put_light_state(user, light, &params, REPEATABLE_STATE_KEYS.contains(&key))
+    }
+
+    /// Set an attribute of a light and return the values the bridge applied.
+    /// 
+    /// Works like `modify_light`, but the bridge's answer is returned as a
+    /// map from the address of each changed attribute, e.g.
+    /// `/lights/1/state/bri`, to the value it was actually set to,
+    /// which can differ from `value` if the bridge clamped it.
+    pub fn modify_light_verbose<T: serde::ser::Serialize>(&self, user: &str, light: &str, key: &str, value: T) -> Result<HashMap<String, serde_json::Value>> {
         let url = format!("{}api/{}/lights/{}/state", self.url_base, user, light);
         let mut params = HashMap::new();
         params.insert(key, value);
-        let response = client
-            .put(&url)
-            .json(&params)
-            .send()?;
-        let response: Vec<ApiResponseSection> = serde_json::from_reader(response)?;
+        let response = self.send(Method::PUT, &url, Some(serde_json::to_vec(&params)?), REPEATABLE_STATE_KEYS.contains(&key))?;
+        let response: Vec<ApiResponseSection> = serde_json::from_slice(&response)?;
+        self.light_change_values(response)
+    }
+
+    /// Change several attributes of a light in a single request.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// Only the attributes which are set in `state` are transmitted.
+    pub fn modify_light_state(&self, user: &str, light: &str, state: &LightStateChange) -> Result<()> {
+        self.put_light_state(user, light, state, state.is_repeatable())
+    }
+
+    /// Apply a change built with [`LightStateChange::new`] to a light.
+    /// 
+    /// This is the same as `modify_light_state`.
+    pub fn apply(&self, user: &str, light: &str, change: &LightStateChange) -> Result<()> {
+        self.modify_light_state(user, light, change)
+    }
+
+    /// Sends `body` as new state of a light and analyzes the response.
+    fn put_light_state<B: serde::ser::Serialize>(&self, user: &str, light: &str, body: &B, retryable: bool) -> Result<()> {
+        let url = format!("{}api/{}/lights/{}/state", self.url_base, user, light);
+        self.put(&url, body, retryable)
+    }
+
+    /// Sends `body` to `url` via PUT and analyzes the response.
+    /// 
+    /// `retryable` tells wether `body` sets absolute values only, so it can be sent twice.
+    fn put<B: serde::ser::Serialize>(&self, url: &str, body: &B, retryable: bool) -> Result<()> {
+        let response = self.send(Method::PUT, url, Some(serde_json::to_vec(body)?), retryable)?;
+        let response: Vec<ApiResponseSection> = serde_json::from_slice(&response)?;
         self.light_change_result(response)
     }
     
@@ -138,8 +803,143 @@ impl Bridge {
     /// specify a user in order to be authenticated.
     pub fn get_all_lights(&self, user: &str) -> Result<HashMap<String, Light>> {
         let url = format!("{}api/{}/lights", self.url_base, user);
-        let response = get(&url)?;
-        Ok(serde_json::from_reader(response)?)
+        self.get(&url)
+    }
+
+    /// Get all lights connected to this bridge, skipping the ones that can't be parsed
+    /// 
+    /// Unlike `get_all_lights`, a single broken device entry doesn't fail
+    /// the whole call. The lights that could be parsed are returned together
+    /// with the identifiers of the others and the reason they were skipped.
+    pub fn get_all_lights_lenient(&self, user: &str) -> Result<(HashMap<String, Light>, UnparseableLights)> {
+        let url = format!("{}api/{}/lights", self.url_base, user);
+        let raw_lights: HashMap<String, serde_json::Value> = self.get(&url)?;
+        let mut lights = HashMap::new();
+        let mut failures = vec![];
+        for (id, raw_light) in raw_lights {
+            match serde_json::from_value(raw_light) {
+                Ok(light) => {
+                    lights.insert(id, light);
+                }
+                Err(e) => failures.push((id, e.into())),
+            }
+        }
+        Ok((lights, failures))
+    }
+
+    /// Get a single light connected to this bridge
+    /// 
+    /// `light` is the identifier of the light. If the bridge doesn't know
+    /// it, the error reported by the bridge is returned.
+    pub fn get_light(&self, user: &str, light: &str) -> Result<Light> {
+        let url = format!("{}api/{}/lights/{}", self.url_base, user, light);
+        self.get(&url)
+    }
+
+    /// Get the current state of a single light, like wether it is on.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    pub fn get_light_state(&self, user: &str, light: &str) -> Result<LightState> {
+        Ok(self.get_light(user, light)?.state)
+    }
+
+    /// Rename a light.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    pub fn set_light_name(&self, user: &str, light: &str, name: &str) -> Result<()> {
+        let url = format!("{}api/{}/lights/{}", self.url_base, user, light);
+        let mut params = HashMap::new();
+        params.insert("name", name);
+        self.put(&url, &params, true)
+    }
+
+    /// Delete a light from the bridge.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// This is irreversible: to use the light again, it has to be
+    /// searched for and paired anew.
+    pub fn delete_light(&self, user: &str, light: &str) -> Result<()> {
+        let url = format!("{}api/{}/lights/{}", self.url_base, user, light);
+        self.delete(&url)
+    }
+
+    /// Start searching for new lights.
+    /// 
+    /// The bridge searches for about a minute. Afterwards, the lights it
+    /// found can be listed with `get_new_lights`.
+    pub fn search_new_lights(&self, user: &str) -> Result<()> {
+        let url = format!("{}api/{}/lights", self.url_base, user);
+        let response = self.post(&url, &serde_json::json!({}))?;
+        self.light_change_result(response)
+    }
+
+    /// List the lights found by the latest search for new lights.
+    pub fn get_new_lights(&self, user: &str) -> Result<NewLights> {
+        let url = format!("{}api/{}/lights/new", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// Requests the resource at `url` via GET and parses it.
+    fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self.send(Method::GET, url, None, true)?;
+        self.resource_result(serde_json::from_slice(&response)?)
+    }
+
+    /// Sends `body` to `url` via POST and returns the parsed response.
+    fn post<B: serde::ser::Serialize>(&self, url: &str, body: &B) -> Result<Vec<ApiResponseSection>> {
+        let response = self.send(Method::POST, url, Some(serde_json::to_vec(body)?), false)?;
+        Ok(serde_json::from_slice(&response)?)
+    }
+
+    /// Sends a DELETE request to `url` and analyzes the response.
+    fn delete(&self, url: &str) -> Result<()> {
+        let response = self.send(Method::DELETE, url, None, false)?;
+        let response: Vec<DeletionResponseSection> = serde_json::from_slice(&response)?;
+        let mut errors = vec![];
+        let mut success = false;
+        for section in response {
+            match section {
+                DeletionResponseSection::Err(e) => errors.push(e),
+                DeletionResponseSection::Success(_) => success = true,
+            }
+        }
+        if success {
+            return Ok(())
+        }
+        Err(errors)?
+    }
+
+    /// List all groups of this bridge
+    /// 
+    /// The special group `0`, which contains all lights, is not listed
+    /// but can be used with `set_group_state` nonetheless.
+    pub fn get_all_groups(&self, user: &str) -> Result<HashMap<String, Group>> {
+        let url = format!("{}api/{}/groups", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// Change the state of all lights in a group at once.
+    /// 
+    /// `group` is the identifier of the group. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_groups`.
+    /// Use `0` to address all lights of the bridge.
+    /// 
+    /// All attributes of `state` are applied to each light of the group,
+    /// e.g. a color for a whole room. Unlike for single lights, `state`
+    /// may also recall a scene.
+    pub fn set_group_state(&self, user: &str, group: &str, state: &LightStateChange) -> Result<()> {
+        let url = format!("{}api/{}/groups/{}/action", self.url_base, user, group);
+        self.put(&url, state, state.is_repeatable())
     }
 
     /// Switch light on / off.
@@ -153,4 +953,1351 @@ impl Bridge {
     pub fn switch_light(&self, user: &str, light: &str, on: bool) -> Result<()> {
         self.modify_light(user, light, "on", on)
     }
+
+    /// Set the brightness of a light.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// The bridge accepts brightness values from 1 to 254, so `bri`
+    /// is clamped to that range: `0` would be rejected and `255`
+    /// would silently saturate.
+    pub fn set_brightness(&self, user: &str, light: &str, bri: u8) -> Result<()> {
+        self.modify_light(user, light, "bri", bri.clamp(1, 254))
+    }
+
+    /// Set the brightness of a light in percent.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// `percent` is mapped from 0.0–100.0 onto the range of 1 to 254 the
+    /// bridge accepts, so 0% is the lowest brightness, not off. Values
+    /// out of range are clamped.
+    pub fn set_brightness_percent(&self, user: &str, light: &str, percent: f32) -> Result<()> {
+        self.set_brightness(user, light, brightness_from_percent(percent))
+    }
+
+    /// Set the color temperature of a light in Kelvin.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// The bridge expects the color temperature in mireds, so `kelvin` is
+    /// converted and the result clamped to the range the light reports, see
+    /// [`Light::clamp_ct`]. This takes an additional request to read the
+    /// capabilities of the light. Without them, the range of 153–500 mireds
+    /// (about 6500 K to 2000 K) all lights accept is used. A `kelvin` of
+    /// zero is an error.
+    pub fn set_color_temperature_kelvin(&self, user: &str, light: &str, kelvin: u16) -> Result<()> {
+        if kelvin == 0 {
+            return Err(Error::Arbitrary(String::from("Color temperature must not be 0 K")));
+        }
+        let mireds = (1_000_000 / u32::from(kelvin)).clamp(153, 500) as u16;
+        let mireds = self.get_light(user, light)?.clamp_ct(mireds);
+        self.modify_light(user, light, "ct", mireds)
+    }
+
+    /// Set hue and saturation of a light in a single request.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// `hue` wraps around from 65535 back to red at 0, `sat` ranges
+    /// from 0 (white) to 254 (fully saturated).
+    pub fn set_hue_saturation(&self, user: &str, light: &str, hue: u16, sat: u8) -> Result<()> {
+        let mut params = HashMap::new();
+        params.insert("hue", serde_json::Value::from(hue));
+        params.insert("sat", serde_json::Value::from(sat.min(254)));
+        self.put_light_state(user, light, &params, true)
+    }
+
+    /// Create a group of lights and return its identifier.
+    /// 
+    /// `lights` are the identifiers of the lights to put into the group.
+    pub fn create_group(&self, user: &str, name: &str, lights: &[String]) -> Result<String> {
+        let url = format!("{}api/{}/groups", self.url_base, user);
+        let body = serde_json::json!({
+            "name": name,
+            "lights": lights,
+            "type": "LightGroup",
+        });
+        let response = self.post(&url, &body)?;
+        self.creation_result(response)
+    }
+
+    /// Create a room and return its identifier.
+    /// 
+    /// Unlike groups created with `create_group`, rooms are shown in the
+    /// Hue app. `lights` are the identifiers of the lights in the room. A
+    /// light can only be in one room.
+    pub fn create_room(&self, user: &str, name: &str, lights: &[String], class: RoomClass) -> Result<String> {
+        let url = format!("{}api/{}/groups", self.url_base, user);
+        let body = serde_json::json!({
+            "name": name,
+            "lights": lights,
+            "type": "Room",
+            "class": class,
+        });
+        let response = self.post(&url, &body)?;
+        self.creation_result(response)
+    }
+
+    /// Delete a group.
+    /// 
+    /// The lights of the group are not affected.
+    pub fn delete_group(&self, user: &str, group: &str) -> Result<()> {
+        let url = format!("{}api/{}/groups/{}", self.url_base, user, group);
+        self.delete(&url)
+    }
+
+    /// Analyzes the response to a request that created a resource
+    /// 
+    /// On success, the bridge reports the identifier of the new resource.
+    fn creation_result(&self, response: Vec<ApiResponseSection>) -> Result<String> {
+        let mut errors = vec![];
+        for section in response {
+            match section {
+                ApiResponseSection::Err(e) => errors.push(e),
+                ApiResponseSection::Success(hashmap) => {
+                    if let Some(id) = hashmap.get("id").and_then(|id| id.as_str()) {
+                        return Ok(String::from(id));
+                    }
+                }
+            }
+        }
+        Err(errors)?
+    }
+
+    /// List all scenes stored on this bridge
+    pub fn get_all_scenes(&self, user: &str) -> Result<HashMap<String, Scene>> {
+        let url = format!("{}api/{}/scenes", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// Recall a scene on the lights of a group.
+    /// 
+    /// `group` is the identifier of the group, `0` addresses all lights.
+    /// `scene` is the identifier of the scene. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_scenes`.
+    pub fn activate_scene(&self, user: &str, group: &str, scene: &str) -> Result<()> {
+        let url = format!("{}api/{}/groups/{}/action", self.url_base, user, group);
+        let mut params = HashMap::new();
+        params.insert("scene", scene);
+        self.put(&url, &params, false)
+    }
+
+    /// Read the configuration of this bridge
+    pub fn get_config(&self, user: &str) -> Result<BridgeConfig> {
+        let url = format!("{}api/{}/config", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// Tells wether a software update is ready to be installed.
+    /// 
+    /// This includes updates for the lights and other devices
+    /// connected to the bridge.
+    pub fn software_update_available(&self, user: &str) -> Result<bool> {
+        let config = self.get_config(user)?;
+        Ok(match config.swupdate2 {
+            Some(update) => matches!(
+                update.state,
+                SoftwareUpdateState::AnyReadyToInstall | SoftwareUpdateState::AllReadyToInstall
+            ),
+            None => false,
+        })
+    }
+
+    /// Install the software updates that are ready to install.
+    /// 
+    /// The bridge and the updated devices may be unavailable while installing.
+    pub fn start_software_update(&self, user: &str) -> Result<()> {
+        let url = format!("{}api/{}/config", self.url_base, user);
+        let body = serde_json::json!({"swupdate2": {"install": true}});
+        self.put(&url, &body, false)
+    }
+
+    /// List the users authorized to access this bridge
+    /// 
+    /// The HashMap keys are the user names, like returned by `register_user`.
+    pub fn get_whitelist(&self, user: &str) -> Result<HashMap<String, WhitelistEntry>> {
+        Ok(self.get_config(user)?.whitelist)
+    }
+
+    /// Revoke the access of a user.
+    /// 
+    /// `target` is the name of the user to delete, which can be
+    /// obtained by listing the HashMap keys of `get_whitelist`.
+    pub fn delete_user(&self, user: &str, target: &str) -> Result<()> {
+        let url = format!("{}api/{}/config/whitelist/{}", self.url_base, user, target);
+        self.delete(&url)
+    }
+
+    /// Save the name of a registered user to a file.
+    /// 
+    /// The file maps the serial numbers of bridges to user names, so
+    /// credentials for several bridges can be kept in the same file.
+    /// Existing entries for other bridges are preserved.
+    /// 
+    /// The file is replaced at once, so it is never left half written. On
+    /// unix, only its owner may read it, since the user name grants access
+    /// to the bridge.
+    /// ```
+    /// use hust::bridge::{Bridge, BridgeDevice};
+    /// 
+    /// let device = BridgeDevice { serial_number: String::from("001788255acc"), ..BridgeDevice::default() };
+    /// let bridge = Bridge::new(String::from("http://192.168.1.5:80/"), device);
+    /// let path = std::env::temp_dir().join("hust-save-credentials-example.json");
+    /// bridge.save_credentials("secret-user", &path).unwrap();
+    /// assert_eq!(bridge.load_credentials(&path).unwrap(), "secret-user");
+    /// #[cfg(unix)]
+    /// {
+    ///     use std::os::unix::fs::PermissionsExt;
+    ///     assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    /// }
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save_credentials(&self, username: &str, path: &Path) -> Result<()> {
+        let mut credentials: HashMap<String, String> = match File::open(path) {
+            Ok(file) => serde_json::from_reader(file)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => Err(e)?,
+        };
+        credentials.insert(self.device.serial_number.clone(), String::from(username));
+        write_replacing(path, &serde_json::to_vec_pretty(&credentials)?)
+    }
+
+    /// Load the name of a user saved with `save_credentials` for this bridge.
+    pub fn load_credentials(&self, path: &Path) -> Result<String> {
+        let mut credentials: HashMap<String, String> = serde_json::from_reader(File::open(path)?)?;
+        match credentials.remove(&self.device.serial_number) {
+            Some(username) => Ok(username),
+            None => Err(Error::Arbitrary(format!(
+                "No credentials for bridge {} in {}",
+                self.device.serial_number,
+                path.display()
+            ))),
+        }
+    }
+
+    /// Registers a user once the button of the bridge is pressed.
+    /// 
+    /// Tries to register a user every second until the button of the
+    /// bridge gets pressed. Returns `Error::Timeout` if this doesn't
+    /// happen within `timeout`. Errors other than the bridge asking for
+    /// the button to be pressed are returned immediately.
+    pub fn register_user_interactive(&self, timeout: Duration) -> Result<String> {
+        let start = Instant::now();
+        loop {
+            match self.register_user() {
+                Err(e) if e.is_link_button_not_pressed() => {}
+                result => return result,
+            }
+            let time_spent = start.elapsed();
+            if time_spent >= timeout {
+                return Err(Error::Timeout);
+            }
+            sleep((timeout - time_spent).min(Duration::from_secs(1)));
+        }
+    }
+
+    /// Set the color of a light from sRGB values.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// The color is converted to CIE xy coordinates and moved into the
+    /// gamut of the light, if the light reports it, see [`Light::clamp_xy`].
+    /// This takes an additional request to read the capabilities of the
+    /// light.
+    pub fn set_rgb(&self, user: &str, light: &str, r: u8, g: u8, b: u8) -> Result<()> {
+        let xy = self.get_light(user, light)?.clamp_xy(color::rgb_to_xy(r, g, b));
+        self.modify_light(user, light, "xy", xy)
+    }
+
+    /// Set the color of a light.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// Only the attributes for the kind of `color` are sent, see
+    /// [`LightStateChange::color`]. RGB colors are moved into the gamut of
+    /// the light like with `set_rgb`, which takes an additional request.
+    pub fn set_color(&self, user: &str, light: &str, color: Color) -> Result<()> {
+        match color {
+            Color::Rgb(r, g, b) => self.set_rgb(user, light, r, g, b),
+            color => self.modify_light_state(user, light, &LightStateChange::new().color(color)),
+        }
+    }
+
+    /// Change the brightness of a light relative to its current brightness.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// `delta` is clamped to -254..=254. The bridge applies the change
+    /// atomically and saturates at the lowest and highest brightness.
+    pub fn adjust_brightness(&self, user: &str, light: &str, delta: i16) -> Result<()> {
+        self.modify_light(user, light, "bri_inc", delta.clamp(-254, 254))
+    }
+
+    /// Let a light signal an alert, e.g. to find out which bulb it is.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// `Alert::Select` blinks once, `Alert::LSelect` breathes for
+    /// 15 seconds and `Alert::None` stops an ongoing alert.
+    pub fn alert(&self, user: &str, light: &str, alert: Alert) -> Result<()> {
+        self.modify_light(user, light, "alert", alert)
+    }
+
+    /// Set the dynamic effect of a light.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// Lights without support for effects make the bridge report an
+    /// error, which is returned as `Error::ApiErrors`.
+    pub fn set_effect(&self, user: &str, light: &str, effect: Effect) -> Result<()> {
+        self.modify_light(user, light, "effect", effect)
+    }
+
+    /// Switch light on / off with a transition of the given duration.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// See [`LightStateChange::transition`] for the supported durations.
+    pub fn switch_light_with_transition(&self, user: &str, light: &str, on: bool, transition: Duration) -> Result<()> {
+        self.modify_light_state(user, light, &LightStateChange::new().on(on).transition(transition))
+    }
+
+    /// Switch a light off if it is on and vice versa, returning the new state.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// This takes two requests, one to read the current state and one to
+    /// change it. If another client switches the light in between, its
+    /// change is overridden.
+    pub fn toggle_light(&self, user: &str, light: &str) -> Result<bool> {
+        let on = !self.get_light(user, light)?.state.on;
+        self.switch_light(user, light, on)?;
+        Ok(on)
+    }
+
+    /// List all sensors connected to this bridge
+    /// 
+    /// Besides physical sensors like motion sensors and switches, the
+    /// bridge also lists virtual ones like the daylight sensor.
+    pub fn get_all_sensors(&self, user: &str) -> Result<HashMap<String, Sensor>> {
+        let url = format!("{}api/{}/sensors", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// Change the configuration of a sensor, e.g. to disable a motion sensor.
+    /// 
+    /// `sensor` is the identifier of the sensor. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_sensors`.
+    pub fn set_sensor_config(&self, user: &str, sensor: &str, config: &SensorConfigChange) -> Result<()> {
+        let url = format!("{}api/{}/sensors/{}/config", self.url_base, user, sensor);
+        self.put(&url, config, true)
+    }
+
+    /// List all automation rules stored on this bridge
+    pub fn get_all_rules(&self, user: &str) -> Result<HashMap<String, Rule>> {
+        let url = format!("{}api/{}/rules", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// List all schedules stored on this bridge
+    pub fn get_all_schedules(&self, user: &str) -> Result<HashMap<String, Schedule>> {
+        let url = format!("{}api/{}/schedules", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// Create a schedule and return its identifier.
+    /// 
+    /// `localtime` is the time the `command` is executed at, see
+    /// [`Schedule::localtime`] for the format.
+    pub fn create_schedule(&self, user: &str, name: &str, description: &str, command: &Command, localtime: &str) -> Result<String> {
+        let url = format!("{}api/{}/schedules", self.url_base, user);
+        let body = serde_json::json!({
+            "name": name,
+            "description": description,
+            "command": command,
+            "localtime": localtime,
+        });
+        let response = self.post(&url, &body)?;
+        self.creation_result(response)
+    }
+
+    /// Delete a schedule.
+    pub fn delete_schedule(&self, user: &str, schedule: &str) -> Result<()> {
+        let url = format!("{}api/{}/schedules/{}", self.url_base, user, schedule);
+        self.delete(&url)
+    }
+
+    /// Find a light by its name, ignoring case.
+    /// 
+    /// Returns the identifier of the light together with the light.
+    pub fn find_light_by_name(&self, user: &str, name: &str) -> Result<Option<(String, Light)>> {
+        let name = name.to_lowercase();
+        Ok(self
+            .get_all_lights(user)?
+            .into_iter()
+            .find(|(_, light)| light.name.to_lowercase() == name))
+    }
+
+    /// Find all lights whose name contains `substring`, ignoring case.
+    /// 
+    /// Returns the identifiers of the lights together with the lights.
+    pub fn find_lights_matching(&self, user: &str, substring: &str) -> Result<Vec<(String, Light)>> {
+        let substring = substring.to_lowercase();
+        Ok(self
+            .get_all_lights(user)?
+            .into_iter()
+            .filter(|(_, light)| light.name.to_lowercase().contains(&substring))
+            .collect())
+    }
+
+    /// Switch several lights on / off in parallel.
+    /// 
+    /// Returns the result for each light, so a single unreachable light
+    /// doesn't hide that the others were switched. To switch all lights
+    /// of the bridge, `set_group_state` on group `0` is faster.
+    /// 
+    /// At most four requests are sent at the same time, since the bridge
+    /// can't handle many more. They are throttled by `with_rate_limit`, too.
+    pub fn switch_lights(&self, user: &str, lights: &[&str], on: bool) -> Vec<(String, Result<()>)> {
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<Option<Result<()>>> = lights.iter().map(|_| None).collect();
+        let finished: Vec<(usize, Result<()>)> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..lights.len().min(MAX_PARALLEL_REQUESTS))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut finished = Vec::new();
+                        // Take the next light until all are switched
+                        loop {
+                            let index = next.fetch_add(1, Ordering::SeqCst);
+                            match lights.get(index) {
+                                Some(light) => finished.push((index, self.switch_light(user, light, on))),
+                                None => return finished,
+                            }
+                        }
+                    })
+                })
+                .collect();
+            workers.into_iter().flat_map(|worker| worker.join().unwrap_or_default()).collect()
+        });
+        for (index, result) in finished {
+            results[index] = Some(result);
+        }
+        lights
+            .iter()
+            .zip(results)
+            .map(|(light, result)| {
+                let result = result.unwrap_or_else(|| Err(Error::Arbitrary(String::from("Switching thread panicked"))));
+                (String::from(*light), result)
+            })
+            .collect()
+    }
+
+    /// Configure what a light does when it is powered on.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// By default, lights turn on at full brightness, e.g. when the
+    /// power comes back after an outage.
+    pub fn set_startup_behavior(&self, user: &str, light: &str, behavior: StartupBehavior) -> Result<()> {
+        let url = format!("{}api/{}/lights/{}/config", self.url_base, user, light);
+        let mut params = HashMap::new();
+        params.insert("startup", behavior);
+        self.put(&url, &params, true)
+    }
+
+    /// Get a handle to change several attributes of a light in one request.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    pub fn light<'a>(&'a self, user: &'a str, light: &'a str) -> LightHandle<'a> {
+        LightHandle::new(self, user, light)
+    }
+
+    /// Receive the changes of lights, buttons, sensors etc. as they happen.
+    /// 
+    /// This connects to the event stream of the API v2, which the bridge
+    /// only offers via HTTPS with its self-signed certificate, so
+    /// `accept_self_signed(true)` is usually needed. The iterator
+    /// blocks until the next event arrives.
+    /// 
+    /// `credentials` are the credentials of a user, registered e.g. with
+    /// `register_user_with_clientkey`.
+    pub fn event_stream(&self, credentials: &Credentials) -> Result<EventStream<Box<dyn Read + Send>>> {
+        let url = format!("{}eventstream/clip/v2", https_url(&self.url_base));
+        let headers = [
+            (APPLICATION_KEY_HEADER, credentials.username.as_str()),
+            ("accept", "text/event-stream"),
+        ];
+        let client;
+        let transport = match &self.transport {
+            Some(transport) => transport.as_ref(),
+            None => {
+                // The stream is endless, so it must not be cut off by a timeout
+                client = Client::builder()
+                    .danger_accept_invalid_certs(self.accept_invalid_certs)
+                    .user_agent(self.user_agent())
+                    .timeout(None)
+                    .build()?;
+                &client as &dyn Transport
+            }
+        };
+        let response = self.attempt(&Method::GET, &url, true, || transport.stream(&url, &headers))?;
+        Ok(EventStream::new(response))
+    }
+
+    /// Send a request to any resource of the API, even one not covered by this crate.
+    /// 
+    /// The request is sent to `{url_base}api/{user}/{path}`, with `body`
+    /// as JSON if given, and the response is parsed into `T`. Like for the
+    /// other methods, a response consisting only of errors results in
+    /// `Error::ApiErrors`.
+    /// 
+    /// ```no_run
+    /// use hust::{Bridge, Result};
+    /// use hust::transport::Method;
+    /// 
+    /// fn capabilities(bridge: &Bridge, user: &str) -> Result<serde_json::Value> {
+    ///     bridge.request(Method::GET, user, "capabilities", None::<&()>)
+    /// }
+    /// ```
+    pub fn request<T, B>(&self, method: Method, user: &str, path: &str, body: Option<&B>) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+        B: serde::ser::Serialize,
+    {
+        let url = format!("{}api/{}/{}", self.url_base, user, path.trim_start_matches('/'));
+        let body = body.map(serde_json::to_vec).transpose()?;
+        // Nothing is known about the effect of other requests
+        let retryable = method == Method::GET;
+        self.resource_result(serde_json::from_slice(&self.send(method, &url, body, retryable)?)?)
+    }
+
+    /// Search the network for the bridge with the given serial number or id.
+    /// 
+    /// `bridge_id` may be the serial number like `001788102201` or the
+    /// bridge id like `001788FFFE102201`, in any case. Returns `None` if
+    /// the bridge didn't answer within `timeout`. The search ends as soon
+    /// as the bridge is found.
+    pub fn discover_by_id(bridge_id: &str, timeout: Duration) -> Result<Option<Bridge>> {
+        let serial_number = serial_number_of(bridge_id);
+        for bridge in BridgeFinder::new(timeout)? {
+            match bridge {
+                Ok(bridge) if bridge.serial_number().eq_ignore_ascii_case(&serial_number) => {
+                    return Ok(Some(bridge))
+                }
+                Ok(_) => {}
+                // The description of another bridge may fail, which doesn't matter here
+                Err(Error::Io(e)) => return Err(e.into()),
+                Err(_) => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Bring a light into a state, e.g. one read before with `get_light`.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// Only the writable attributes of `state` are sent, see [`LightState::to_change`].
+    pub fn apply_state(&self, user: &str, light: &str, state: &LightState) -> Result<()> {
+        self.modify_light_state(user, light, &state.to_change())
+    }
+
+    /// Read the states of all lights, e.g. to `restore` them later.
+    pub fn snapshot(&self, user: &str) -> Result<HashMap<String, LightState>> {
+        Ok(self
+            .get_all_lights(user)?
+            .into_iter()
+            .map(|(id, light)| (id, light.state))
+            .collect())
+    }
+
+    /// Bring the lights back into the states of a `snapshot`.
+    /// 
+    /// Returns the result for each light, so a single failing light
+    /// doesn't keep the others from being restored. Lights which were
+    /// unreachable when the snapshot was taken are not changed, since their
+    /// state was unknown, and reported as failed.
+    pub fn restore(&self, user: &str, snapshot: &HashMap<String, LightState>) -> Vec<(String, Result<()>)> {
+        snapshot
+            .iter()
+            .map(|(light, state)| {
+                let result = if state.reachable {
+                    self.apply_state(user, light, state)
+                } else {
+                    Err(Error::Unreachable(light.clone()))
+                };
+                (light.clone(), result)
+            })
+            .collect()
+    }
+
+    /// Tells wether the bridge can reach a light.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// The bridge accepts changes of unreachable lights, e.g. lights
+    /// which are powered off, but they don't take effect.
+    pub fn is_reachable(&self, user: &str, light: &str) -> Result<bool> {
+        Ok(self.get_light(user, light)?.state.reachable)
+    }
+
+    /// Set an attribute of a light, if the light is reachable.
+    /// 
+    /// Works like `modify_light`, but returns `Error::Unreachable` instead
+    /// of sending a change that wouldn't take effect. This takes an
+    /// additional request to check that the light is reachable.
+    pub fn modify_light_checked<T: serde::ser::Serialize>(&self, user: &str, light: &str, key: &str, value: T) -> Result<()> {
+        if !self.is_reachable(user, light)? {
+            return Err(Error::Unreachable(String::from(light)));
+        }
+        self.modify_light(user, light, key, value)
+    }
+
+    /// List all resource links stored on this bridge
+    /// 
+    /// Applications use them to tie resources together, e.g. the scenes
+    /// of a room with its group.
+    pub fn get_resourcelinks(&self, user: &str) -> Result<HashMap<String, ResourceLink>> {
+        let url = format!("{}api/{}/resourcelinks", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// List the time zones the bridge knows, like `Europe/Berlin`
+    pub fn get_timezones(&self, user: &str) -> Result<Vec<String>> {
+        let url = format!("{}api/{}/info/timezones", self.url_base, user);
+        self.get(&url)
+    }
+
+    /// Set the time zone of the bridge, which schedules are evaluated in.
+    /// 
+    /// `timezone` has to be one of the time zones listed by `get_timezones`,
+    /// which is checked before the change is sent.
+    pub fn set_timezone(&self, user: &str, timezone: &str) -> Result<()> {
+        if !self.get_timezones(user)?.iter().any(|known| known == timezone) {
+            return Err(Error::Arbitrary(format!("Unknown time zone {}", timezone)));
+        }
+        let url = format!("{}api/{}/config", self.url_base, user);
+        self.put(&url, &serde_json::json!({ "timezone": timezone }), true)
+    }
+
+    /// Take over lights close to the bridge, even from other bridges.
+    /// 
+    /// **This resets lights and removes them from the bridge they belong to.**
+    /// The bridge searches for lights within about 30 cm for some seconds
+    /// and resets them to factory settings, so they lose their name, their
+    /// scenes and their startup behavior. Afterwards, they can be found as
+    /// new lights with `search_new_lights`. Lights of another bridge then
+    /// stop working with that bridge.
+    /// 
+    /// Use this to move lights from one bridge to another, or to recover
+    /// lights which can't be found otherwise.
+    pub fn touchlink(&self, user: &str) -> Result<()> {
+        let url = format!("{}api/{}/config", self.url_base, user);
+        self.put(&url, &serde_json::json!({ "touchlink": true }), false)
+    }
+
+    /// Change the Zigbee channel the bridge communicates with the lights on.
+    /// 
+    /// Switching to a channel farther away from the one of the WiFi can
+    /// help if lights are often unreachable. `channel` has to be one of
+    /// [`ZIGBEE_CHANNELS`]. The lights follow the bridge to the new
+    /// channel, which can take a minute.
+    pub fn set_zigbee_channel(&self, user: &str, channel: u8) -> Result<()> {
+        if !ZIGBEE_CHANNELS.contains(&channel) {
+            return Err(Error::Arbitrary(format!(
+                "Zigbee channel {} isn't one of {:?}",
+                channel, ZIGBEE_CHANNELS
+            )));
+        }
+        let url = format!("{}api/{}/config", self.url_base, user);
+        self.put(&url, &serde_json::json!({ "zigbeechannel": channel }), true)
+    }
+
+    /// Create a room for each entry of `rooms` and return their identifiers.
+    /// 
+    /// `rooms` maps the names of the rooms to the identifiers of the
+    /// lights in them. A light can only be in one room. The rooms are of
+    /// the class `Other`. Returns the result for each room, so a single
+    /// failing room doesn't hide that the others were created.
+    pub fn create_groups(&self, user: &str, rooms: &HashMap<String, Vec<String>>) -> Vec<(String, Result<String>)> {
+        rooms
+            .iter()
+            .map(|(name, lights)| (name.clone(), self.create_room(user, name, lights, RoomClass::Other)))
+            .collect()
+    }
+
+    /// Wait until the state of a light fulfills `predicate`.
+    /// 
+    /// `user` is the user you had to register with `register_user`.
+    /// 
+    /// `light` is the identifier of the light. All identifiers can
+    /// be obtained by listing the HashMap keys of `get_all_lights`.
+    /// 
+    /// The state is read every 250ms. Returns wether `predicate` was
+    /// fulfilled within `timeout`. This helps to make sure a change took
+    /// effect, since lights may apply changes with some delay:
+    /// ```no_run
+    /// use hust::{Bridge, Result};
+    /// use std::time::Duration;
+    /// 
+    /// fn switch_on(bridge: &Bridge, user: &str) -> Result<bool> {
+    ///     bridge.switch_light(user, "1", true)?;
+    ///     bridge.wait_for_state(user, "1", |state| state.on, Duration::from_secs(2))
+    /// }
+    /// ```
+    pub fn wait_for_state(&self, user: &str, light: &str, predicate: impl Fn(&LightState) -> bool, timeout: Duration) -> Result<bool> {
+        let start = Instant::now();
+        loop {
+            if predicate(&self.get_light(user, light)?.state) {
+                return Ok(true);
+            }
+            let time_spent = start.elapsed();
+            if time_spent >= timeout {
+                return Ok(false);
+            }
+            sleep((timeout - time_spent).min(STATE_POLL_INTERVAL));
+        }
+    }
+
+    /// Read how many resources of each kind the bridge can store
+    /// 
+    /// Creating a resource fails with an error whose kind tells
+    /// [`ApiErrorKind::is_capacity_exceeded`](crate::error::ApiErrorKind::is_capacity_exceeded)
+    /// once there's no space left for it.
+    pub fn get_capabilities(&self, user: &str) -> Result<Capabilities> {
+        let url = format!("{}api/{}/capabilities", self.url_base, user);
+        self.get(&url)
+    }
+}
+
+#[cfg(feature = "async")]
+impl Bridge {
+    /// Builds the HTTP client for the `*_async` methods.
+    /// 
+    /// Panics like `reqwest::Client::new` if the client can't be built.
+    fn build_async_client(&self) -> reqwest::Client {
+        reqwest::Client::builder()
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .user_agent(self.user_agent())
+            .build()
+            .expect("Failed to build HTTP client")
+    }
+
+    /// Creates a Bridge object from a description URL, without blocking.
+    pub async fn from_description_url_async(url: String) -> Result<Bridge> {
+        let client = reqwest::Client::builder().user_agent(DEFAULT_USER_AGENT).build()?;
+        let response = client.get(&url).send().await?.text().await?;
+        let mut bridge = Bridge::from_description(&response)?;
+        bridge.async_client = client;
+        Ok(bridge)
+    }
+
+    /// Registers a user and return its name, without blocking.
+    /// 
+    /// See `register_user`.
+    pub async fn register_user_async(&self) -> Result<String> {
+        let url = format!("{}api", self.url_base);
+        let devicetype = format!("{}#{}", DEFAULT_APP_NAME, DEFAULT_DEVICE_NAME);
+        let mut params = HashMap::new();
+        params.insert("devicetype", devicetype.as_str());
+        let response = checked_status(self.async_client.post(&url).json(&params).send().await?).await?;
+        let response: Vec<ApiResponseSection> = response.json().await?;
+        Ok(self.registration_result(response)?.username)
+    }
+
+    /// Set an attribute of a light, without blocking.
+    /// 
+    /// See `modify_light`.
+    pub async fn modify_light_async<T: serde::ser::Serialize>(&self, user: &str, light: &str, key: &str, value: T) -> Result<()> {
+        let url = format!("{}api/{}/lights/{}/state", self.url_base, user, light);
+        let mut params = HashMap::new();
+        params.insert(key, value);
+        if let Some(rate_limiter) = self.rate_limiter(&Method::PUT, &url) {
+            tokio::time::sleep(rate_limiter.reserve()).await;
+        }
+        let response = checked_status(self.async_client.put(&url).json(&params).send().await?).await?;
+        let response: Vec<ApiResponseSection> = response.json().await?;
+        self.light_change_result(response)
+    }
+
+    /// List all lights connected to this bridge, without blocking.
+    /// 
+    /// See `get_all_lights`.
+    pub async fn get_all_lights_async(&self, user: &str) -> Result<HashMap<String, Light>> {
+        let url = format!("{}api/{}/lights", self.url_base, user);
+        let response = checked_status(self.async_client.get(&url).send().await?).await?;
+        Ok(response.json().await?)
+    }
+}
+
+/// The HTTP client for the `*_async` methods of new bridges, sending the default user agent.
+/// 
+/// Panics like `reqwest::Client::new` if the client can't be built.
+#[cfg(feature = "async")]
+fn default_async_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(DEFAULT_USER_AGENT)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Turns responses with an HTTP error status into `Error::Http`.
+#[cfg(feature = "async")]
+async fn checked_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    Err(Error::Http {
+        status: status.as_u16(),
+        body: response.text().await.unwrap_or_default(),
+    })
+}
+
+#[cfg(feature = "v2")]
+impl Bridge {
+    /// Sends a request to the API v2 and returns the resources of the response.
+    /// 
+    /// `path` is relative to `clip/v2/`, like `resource/light`.
+    fn request_v2<T, B>(&self, method: Method, credentials: &Credentials, path: &str, body: Option<&B>) -> Result<Vec<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        B: serde::ser::Serialize,
+    {
+        let url = format!("{}clip/v2/{}", https_url(&self.url_base), path);
+        let body = match body {
+            Some(body) => Some(serde_json::to_vec(body)?),
+            None => None,
+        };
+        let headers = [(APPLICATION_KEY_HEADER, credentials.username.as_str())];
+        let retryable = method == Method::GET;
+        let response = match self.send_with_headers(method, &url, &headers, body, retryable) {
+            Ok(response) => response,
+            // Errors are described in the body, but not every status has one
+            Err(Error::Http { status, body }) => {
+                return Err(match serde_json::from_str::<v2::Response<serde_json::Value>>(&body) {
+                    Ok(response) if !response.errors.is_empty() => v2_errors(status, path, response.errors),
+                    _ => Error::Http { status, body },
+                })
+            }
+            Err(e) => return Err(e),
+        };
+        let response: v2::Response<T> = serde_json::from_slice(&response)?;
+        if !response.errors.is_empty() {
+            return Err(v2_errors(200, path, response.errors));
+        }
+        Ok(response.data)
+    }
+
+    /// List all lights connected to this bridge, via the API v2
+    /// 
+    /// The API v2 is only offered via HTTPS with the self-signed
+    /// certificate of the bridge, so `accept_self_signed(true)` is
+    /// usually needed.
+    pub fn get_lights_v2(&self, credentials: &Credentials) -> Result<Vec<LightV2>> {
+        self.request_v2(Method::GET, credentials, "resource/light", None::<&()>)
+    }
+
+    /// Get a single light connected to this bridge, via the API v2
+    /// 
+    /// `light` is the UUID of the light, as listed by `get_lights_v2`.
+    /// 
+    /// Errors reported by the bridge result in `Error::ApiErrors`, e.g.
+    /// with `ResourceNotAvailable` if there is no such light.
+    pub fn get_light_v2(&self, credentials: &Credentials, light: &str) -> Result<LightV2> {
+        let path = format!("resource/light/{}", light);
+        self.request_v2(Method::GET, credentials, &path, None::<&()>)?
+            .pop()
+            .ok_or_else(|| Error::Arbitrary(format!("Light {} not found", light)))
+    }
+
+    /// Set the colors of a gradient light, like a gradient lightstrip.
+    /// 
+    /// `light` is the UUID of the light, as listed by `get_lights_v2`.
+    /// 
+    /// `points` are the colors in the CIE color space, from the start to
+    /// the end of the light. At least two points are needed, and at most as
+    /// many as the light supports, which is checked before the change is sent.
+    pub fn set_gradient(&self, credentials: &Credentials, light: &str, points: &[[f32; 2]]) -> Result<()> {
+        let points_capable = match self.get_light_v2(credentials, light)?.gradient {
+            Some(gradient) => gradient.points_capable,
+            None => return Err(Error::Arbitrary(format!("Light {} doesn't support gradients", light))),
+        };
+        if points.len() < 2 || points.len() > usize::from(points_capable) {
+            return Err(Error::Arbitrary(format!(
+                "Light {} supports gradients of 2 to {} points, not {}",
+                light,
+                points_capable,
+                points.len()
+            )));
+        }
+        let points: Vec<GradientPoint> = points.iter().map(|&xy| xy.into()).collect();
+        let body = serde_json::json!({"gradient": {"points": points}});
+        let path = format!("resource/light/{}", light);
+        self.request_v2::<serde_json::Value, _>(Method::PUT, credentials, &path, Some(&body))?;
+        Ok(())
+    }
+
+    /// List the entertainment areas of this bridge, with their channels
+    /// 
+    /// The `id` of an area is needed to stream colors to it, while the
+    /// positions of its channels tell which color to stream to each one.
+    pub fn get_entertainment_areas(&self, credentials: &Credentials) -> Result<Vec<EntertainmentArea>> {
+        self.request_v2(Method::GET, credentials, "resource/entertainment_configuration", None::<&()>)
+    }
+}
+
+#[cfg(feature = "entertainment")]
+impl Bridge {
+    /// Start streaming colors to the lights of an entertainment area.
+    /// 
+    /// `credentials` must contain the `clientkey`, so the user has to be
+    /// registered with `register_user_with_clientkey`. `area` is the UUID
+    /// of the entertainment area, as listed by `get_entertainment_areas`.
+    /// 
+    /// The colors are sent via DTLS to the bridge, which must be reachable
+    /// via HTTPS for the API v2 as well, see `get_lights_v2`:
+    /// ```no_run
+    /// use hust::Bridge;
+    /// # fn example(bridge: Bridge, credentials: hust::bridge::Credentials) -> hust::Result<()> {
+    /// let mut session = bridge.start_streaming(&credentials, "1a8d99cc-967b-44f2-9202-43f976c0fa6b")?;
+    /// for step in 0..=255u16 {
+    ///     session.send_colors(&[(0, [step * 257, 0, 0]), (1, [0, 0, step * 257])])?;
+    ///     std::thread::sleep(std::time::Duration::from_millis(20));
+    /// }
+    /// session.stop()
+    /// # }
+    /// ```
+    pub fn start_streaming(&self, credentials: &Credentials, area: &str) -> Result<StreamSession> {
+        self.set_streaming(credentials, area, true)?;
+        StreamSession::connect(self.clone(), credentials.clone(), String::from(area)).inspect_err(|_| {
+            // Give the lights free again, which the bridge reserved for the stream
+            let _ = self.set_streaming(credentials, area, false);
+        })
+    }
+
+    /// Activate or deactivate streaming to the entertainment area `area`.
+    pub(crate) fn set_streaming(&self, credentials: &Credentials, area: &str, active: bool) -> Result<()> {
+        let path = format!("resource/entertainment_configuration/{}", area);
+        let body = serde_json::json!({"action": if active { "start" } else { "stop" }});
+        self.request_v2::<serde_json::Value, _>(Method::PUT, credentials, &path, Some(&body))?;
+        Ok(())
+    }
+}
+
+/// Maps a percentage onto the brightness range of 1 to 254.
+fn brightness_from_percent(percent: f32) -> u8 {
+    let percent = if percent.is_nan() { 0.0 } else { percent.clamp(0.0, 100.0) };
+    1 + (percent / 100.0 * 253.0).round() as u8
+}
+
+/// Rewrites a device description into the plain form `serde_xml_rs` copes with.
+/// 
+/// Anything before or after the root element, like whitespace or a byte
+/// order mark, is dropped. So are elements of namespaces other than the UPnP one, e.g.
+/// vendor extensions, whose names could collide with the UPnP elements.
+/// The remaining elements lose their prefixes and attributes.
+fn normalize_description(xml: &str) -> std::result::Result<String, xml::reader::Error> {
+    let xml = xml.trim_start_matches(|c: char| c == '\u{feff}' || c.is_whitespace());
+    let mut normalized = String::with_capacity(xml.len());
+    let mut depth = 0;
+    // Depth of the element that is left out with all its content
+    let mut skipped_at = None;
+    for event in EventReader::from_str(xml) {
+        match event? {
+            XmlEvent::StartElement { name, .. } => {
+                depth += 1;
+                if skipped_at.is_none() && name.namespace.as_deref().is_some_and(|ns| ns != UPNP_DEVICE_NAMESPACE) {
+                    skipped_at = Some(depth);
+                } else if skipped_at.is_none() {
+                    normalized.push_str(&format!("<{}>", name.local_name));
+                }
+            }
+            XmlEvent::EndElement { name } => {
+                if skipped_at.is_none() {
+                    normalized.push_str(&format!("</{}>", name.local_name));
+                } else if skipped_at == Some(depth) {
+                    skipped_at = None;
+                }
+                depth -= 1;
+                // Ignore anything after the root element, which some bridges pad with zeros
+                if depth == 0 {
+                    break;
+                }
+            }
+            XmlEvent::Characters(text) | XmlEvent::CData(text) if skipped_at.is_none() => {
+                normalized.push_str(&escape_str_pcdata(&text));
+            }
+            _ => {}
+        }
+    }
+    Ok(normalized)
+}
+
+/// Forms the bridge id from a serial number, as the inverse of `serial_number_of`.
+/// 
+/// Serial numbers which aren't MAC addresses are only uppercased.
+pub(crate) fn bridge_id_of(serial_number: &str) -> String {
+    if serial_number.len() == 12 && serial_number.chars().all(|c| c.is_ascii_hexdigit()) {
+        format!("{}FFFE{}", &serial_number[..6], &serial_number[6..]).to_ascii_uppercase()
+    } else {
+        serial_number.to_ascii_uppercase()
+    }
+}
+
+/// Turns a bridge id like `001788FFFE102201` into the serial number `001788102201`.
+/// 
+/// Serial numbers are returned unchanged.
+fn serial_number_of(bridge_id: &str) -> String {
+    if bridge_id.len() == 16 && bridge_id.get(6..10).is_some_and(|infix| infix.eq_ignore_ascii_case("FFFE")) {
+        format!("{}{}", &bridge_id[..6], &bridge_id[10..])
+    } else {
+        String::from(bridge_id)
+    }
+}
+
+/// Logs the outcome and duration of a request.
+/// 
+/// The user name in the URL is left out, since it grants access to the bridge.
+#[cfg(feature = "log")]
+fn log_request<T: ResponseBody>(method: &Method, url: &str, response: &Result<T>, elapsed: Duration) {
+    let url = match url.split_once("api/") {
+        Some((base, rest)) => match rest.split_once('/') {
+            Some((_user, path)) => format!("{}api/<user>/{}", base, path),
+            None => format!("{}api/<user>", base),
+        },
+        None => String::from(url),
+    };
+    match response {
+        Ok(body) => match body.size() {
+            Some(size) => log::debug!("{} {} succeeded in {:?} ({} bytes)", method, url, elapsed, size),
+            None => log::debug!("{} {} succeeded in {:?}", method, url, elapsed),
+        },
+        Err(Error::Http { status, .. }) => log::debug!("{} {} failed in {:?} with status {}", method, url, elapsed, status),
+        Err(e) => log::debug!("{} {} failed in {:?}: {}", method, url, elapsed, e),
+    }
+}
+
+/// A response body, as returned by a transport
+trait ResponseBody {
+    /// The size of the body, if it was received completely
+    #[cfg_attr(not(feature = "log"), allow(dead_code))]
+    fn size(&self) -> Option<usize>;
+}
+
+impl ResponseBody for Vec<u8> {
+    fn size(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl ResponseBody for Box<dyn Read + Send> {
+    fn size(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Turns the errors of an API v2 response into the errors of the API v1 they correspond to.
+/// 
+/// The API v2 only describes its errors, so their kind is derived from the HTTP `status`.
+#[cfg(feature = "v2")]
+fn v2_errors(status: u16, path: &str, errors: Vec<v2::ResponseError>) -> Error {
+    let error_type = match status {
+        401 | 403 => 1,
+        404 => 3,
+        405 => 4,
+        429 | 500..=599 => 901,
+        _ => 7,
+    };
+    let errors = errors
+        .into_iter()
+        .map(|e| ApiError {
+            error_type,
+            address: format!("/{}", path),
+            description: e.description,
+        })
+        .collect();
+    Error::ApiErrors(errors)
+}
+
+/// Replaces the file at `path` with `contents`, readable only by its owner on unix.
+/// 
+/// The contents are written to a temporary file next to it first, which
+/// is then renamed, so a crash never leaves an empty or partial file.
+fn write_replacing(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temporary_name = std::ffi::OsString::from(".");
+    temporary_name.push(path.file_name().unwrap_or(path.as_os_str()));
+    temporary_name.push(".tmp");
+    let temporary = path.with_file_name(temporary_name);
+    // A leftover of an earlier crash may have other permissions
+    let _ = fs::remove_file(&temporary);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&temporary).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    match written.and_then(|_| fs::rename(&temporary, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&temporary);
+            Err(e.into())
+        }
+    }
+}
+
+/// Tells wether a request failed because of a condition which may pass.
+fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::Http { status, .. } => *status == 429 || *status >= 500,
+        Error::Reqwest(e) => e.is_timeout() || e.is_connect(),
+        _ => false,
+    }
+}
+
+/// Turns an `http://` URL into the `https://` URL of the same resource.
+/// 
+/// The port 80 is dropped, so the default HTTPS port is used.
+fn https_url(url: &str) -> String {
+    match url.strip_prefix("http://") {
+        Some(rest) => {
+            let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+            let host = host.strip_suffix(":80").unwrap_or(host);
+            format!("https://{}{}", host, path)
+        }
+        None => String::from(url),
+    }
+}
+
+/// Prints the bridge like `Philips hue (192.168.1.5) (Philips hue bridge 2015, 001788102201)`
+impl fmt::Display for Bridge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}, {})", self.user_readable_identifier(), self.model_name(), self.serial_number())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// The answer of the bridge to switching a light
+    const SWITCHED: &[u8] = br#"[{"success": {"/lights/1/state/on": true}}]"#;
+
+    /// A light with the gamut C
+    const LIGHT: &[u8] = br#"{"uniqueid": "00:17:88:01:00:bd:c7:b9-0b", "type": "Extended color light",
+        "name": "Lamp", "modelid": "LCT015", "manufacturername": "Signify Netherlands B.V.",
+        "swversion": "1.46.13", "state": {"on": true, "reachable": true},
+        "capabilities": {"control": {"colorgamut": [[0.6915, 0.3083], [0.17, 0.7], [0.1532, 0.0475]]}}}"#;
+
+    /// A request as received by the fake bridge
+    type Request = (Method, String, Option<serde_json::Value>);
+
+    /// Answers requests like a bridge and remembers them
+    #[derive(Debug)]
+    struct FakeBridge {
+        /// Answers a request, given its method and how many requests came before
+        answer: fn(&Method, usize) -> Result<Vec<u8>>,
+        /// How long each request takes
+        delay: Duration,
+        requests: Mutex<Vec<Request>>,
+        running: AtomicUsize,
+        /// How many requests were handled at most at the same time
+        most_running: AtomicUsize,
+    }
+
+    impl FakeBridge {
+        fn new(answer: fn(&Method, usize) -> Result<Vec<u8>>) -> Arc<FakeBridge> {
+            FakeBridge::slow(answer, Duration::ZERO)
+        }
+
+        fn slow(answer: fn(&Method, usize) -> Result<Vec<u8>>, delay: Duration) -> Arc<FakeBridge> {
+            Arc::new(FakeBridge {
+                answer,
+                delay,
+                requests: Mutex::new(Vec::new()),
+                running: AtomicUsize::new(0),
+                most_running: AtomicUsize::new(0),
+            })
+        }
+
+        /// A bridge sending its requests to this fake
+        fn bridge(self: &Arc<Self>) -> Bridge {
+            Bridge::new(String::from("http://bridge/"), BridgeDevice::default()).with_transport(self.clone())
+        }
+
+        fn requests(&self) -> Vec<Request> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl Transport for FakeBridge {
+        fn request(&self, method: Method, url: &str, body: Option<Vec<u8>>) -> Result<Vec<u8>> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_running.fetch_max(running, Ordering::SeqCst);
+            sleep(self.delay);
+            let body = body.map(|body| serde_json::from_slice(&body).unwrap());
+            let number = {
+                let mut requests = self.requests.lock().unwrap();
+                requests.push((method.clone(), String::from(url), body));
+                requests.len() - 1
+            };
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            (self.answer)(&method, number)
+        }
+    }
+
+    /// Is busy for the first request
+    fn busy_once(_: &Method, number: usize) -> Result<Vec<u8>> {
+        if number == 0 {
+            return Err(Error::Http { status: 503, body: String::new() });
+        }
+        Ok(SWITCHED.to_vec())
+    }
+
+    #[test]
+    fn switch_light_sends_state() {
+        let fake = FakeBridge::new(|_, _| Ok(SWITCHED.to_vec()));
+        fake.bridge().switch_light("user", "1", true).unwrap();
+        let requests = fake.requests();
+        assert_eq!(requests[0].0, Method::PUT);
+        assert_eq!(requests[0].1, "http://bridge/api/user/lights/1/state");
+        assert_eq!(requests[0].2, Some(serde_json::json!({"on": true})));
+    }
+
+    #[test]
+    fn bridge_is_shared_between_threads() {
+        let fake = FakeBridge::new(|_, _| Ok(SWITCHED.to_vec()));
+        let bridge = Arc::new(fake.bridge());
+        let threads: Vec<_> = (1..=8)
+            .map(|light| {
+                let bridge = bridge.clone();
+                std::thread::spawn(move || bridge.switch_light("user", &light.to_string(), true))
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap().unwrap();
+        }
+        assert_eq!(fake.requests().len(), 8);
+    }
+
+    #[test]
+    fn absolute_changes_are_retried() {
+        let fake = FakeBridge::new(busy_once);
+        assert!(fake.bridge().with_retry(3).switch_light("user", "1", true).is_ok());
+        assert_eq!(fake.requests().len(), 2);
+    }
+
+    #[test]
+    fn relative_changes_and_actions_are_not_retried() {
+        let fake = FakeBridge::new(busy_once);
+        assert!(fake.bridge().with_retry(3).adjust_brightness("user", "1", 10).is_err());
+        assert_eq!(fake.requests().len(), 1);
+
+        let fake = FakeBridge::new(busy_once);
+        assert!(fake.bridge().with_retry(3).touchlink("user").is_err());
+        assert_eq!(fake.requests().len(), 1);
+    }
+
+    #[test]
+    fn set_rgb_clamps_to_gamut() {
+        let fake = FakeBridge::new(|method, _| match *method {
+            Method::GET => Ok(LIGHT.to_vec()),
+            _ => Ok(SWITCHED.to_vec()),
+        });
+        fake.bridge().set_rgb("user", "1", 255, 0, 0).unwrap();
+        let xy: [f32; 2] = serde_json::from_value(fake.requests()[1].2.as_ref().unwrap()["xy"].clone()).unwrap();
+        // Pure red lies outside of the gamut, so the closest red of the light is used
+        assert!(xy[0] <= 0.6915 + 1e-4);
+    }
+
+    #[test]
+    fn set_color_clamps_rgb_like_set_rgb() {
+        let fake = FakeBridge::new(|method, _| match *method {
+            Method::GET => Ok(LIGHT.to_vec()),
+            _ => Ok(SWITCHED.to_vec()),
+        });
+        fake.bridge().set_rgb("user", "1", 255, 0, 0).unwrap();
+        fake.bridge().set_color("user", "1", Color::Rgb(255, 0, 0)).unwrap();
+        let requests = fake.requests();
+        assert_eq!(requests[1].2, requests[3].2);
+    }
+
+    #[test]
+    fn switch_lights_limits_parallel_requests() {
+        let fake = FakeBridge::slow(|_, _| Ok(SWITCHED.to_vec()), Duration::from_millis(20));
+        let lights: Vec<String> = (1..=12).map(|light| light.to_string()).collect();
+        let lights: Vec<&str> = lights.iter().map(String::as_str).collect();
+        let results = fake.bridge().switch_lights("user", &lights, true);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert_eq!(results[11].0, "12");
+        assert!(fake.most_running.load(Ordering::SeqCst) <= MAX_PARALLEL_REQUESTS);
+    }
+
+    #[cfg(feature = "v2")]
+    #[test]
+    fn v2_errors_are_typed() {
+        let fake = FakeBridge::new(|_, _| {
+            Err(Error::Http {
+                status: 404,
+                body: String::from(r#"{"errors": [{"description": "Not Found"}], "data": []}"#),
+            })
+        });
+        let credentials = Credentials { username: String::from("user"), clientkey: None };
+        match fake.bridge().get_light_v2(&credentials, "1") {
+            Err(Error::ApiErrors(errors)) => assert_eq!(errors[0].kind(), crate::error::ApiErrorKind::ResourceNotAvailable),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,108 @@
+/// A color gamut as the CIE xy coordinates of its red, green and blue corners
+pub type Gamut = [[f32; 2]; 3];
+
+/// Gamut of older color lights like LivingColors
+pub const GAMUT_A: Gamut = [[0.704, 0.296], [0.2151, 0.7106], [0.138, 0.08]];
+
+/// Gamut of the first Hue color bulbs
+pub const GAMUT_B: Gamut = [[0.675, 0.322], [0.409, 0.518], [0.167, 0.04]];
+
+/// Gamut of newer Hue color lights
+pub const GAMUT_C: Gamut = [[0.6915, 0.3083], [0.17, 0.7], [0.1532, 0.0475]];
+
+/// A color in one of the ways a light can be told to show it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    /// White of a color temperature in mireds, from 153 (cold) to 500 (warm)
+    Temperature(u16),
+    /// Hue from 0 to 65535 and saturation from 0 to 254
+    HueSat { hue: u16, sat: u8 },
+    /// CIE xy coordinates
+    Xy([f32; 2]),
+    /// sRGB values, which are sent as CIE xy coordinates
+    Rgb(u8, u8, u8),
+}
+
+/// The white point of the conversion, used for black
+const WHITE_POINT: [f32; 2] = [0.3127, 0.329];
+
+/// Removes the sRGB gamma correction from a color channel.
+fn linearize(channel: u8) -> f32 {
+    let value = f32::from(channel) / 255.0;
+    if value > 0.04045 {
+        ((value + 0.055) / 1.055).powf(2.4)
+    } else {
+        value / 12.92
+    }
+}
+
+/// Converts an sRGB color to CIE xy coordinates.
+///
+/// This uses the Wide RGB D65 conversion recommended by Philips.
+/// The result may lie outside the gamut of a light, see [`clamp_to_gamut`].
+///
+/// Example:
+/// ```
+/// use hust::color::rgb_to_xy;
+///
+/// let [x, y] = rgb_to_xy(255, 255, 255);
+/// assert!((x - 0.3227).abs() < 0.001);
+/// assert!((y - 0.329).abs() < 0.001);
+/// ```
+pub fn rgb_to_xy(r: u8, g: u8, b: u8) -> [f32; 2] {
+    let (r, g, b) = (linearize(r), linearize(g), linearize(b));
+    let x = r * 0.664_511 + g * 0.154_324 + b * 0.162_028;
+    let y = r * 0.283_881 + g * 0.668_433 + b * 0.047_685;
+    let z = r * 0.000_088 + g * 0.072_310 + b * 0.986_039;
+    let sum = x + y + z;
+    if sum == 0.0 {
+        return WHITE_POINT;
+    }
+    [x / sum, y / sum]
+}
+
+/// The z component of the cross product of `b - a` and `p - a`.
+fn cross(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// The point on the line segment from `a` to `b` that is closest to `p`.
+fn closest_on_segment(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> [f32; 2] {
+    let ab = [b[0] - a[0], b[1] - a[1]];
+    let length = ab[0] * ab[0] + ab[1] * ab[1];
+    if length == 0.0 {
+        return a;
+    }
+    let t = (((p[0] - a[0]) * ab[0] + (p[1] - a[1]) * ab[1]) / length).clamp(0.0, 1.0);
+    [a[0] + t * ab[0], a[1] + t * ab[1]]
+}
+
+/// Squared distance between two points.
+fn distance2(a: [f32; 2], b: [f32; 2]) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)
+}
+
+/// Moves a color into a gamut.
+///
+/// Colors within the gamut are returned unchanged, others are replaced
+/// by the closest color on the border of the gamut triangle.
+pub fn clamp_to_gamut(xy: [f32; 2], gamut: &Gamut) -> [f32; 2] {
+    let [red, green, blue] = *gamut;
+    let d1 = cross(red, green, xy);
+    let d2 = cross(green, blue, xy);
+    let d3 = cross(blue, red, xy);
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    if !(has_negative && has_positive) {
+        return xy;
+    }
+    [
+        closest_on_segment(red, green, xy),
+        closest_on_segment(green, blue, xy),
+        closest_on_segment(blue, red, xy),
+    ]
+    .iter()
+    .copied()
+    .min_by(|a, b| distance2(*a, xy).total_cmp(&distance2(*b, xy)))
+    .unwrap_or(xy)
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The Zigbee channels a bridge can use, chosen to overlap little with WiFi
+pub const ZIGBEE_CHANNELS: [u8; 4] = [11, 15, 20, 25];
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Configuration of a bridge
+///
+/// Only the most common attributes are captured, others are ignored.
+pub struct BridgeConfig {
+    pub name: String,
+    /// Software version of the bridge
+    pub swversion: String,
+    /// Version of the API the bridge provides, like `"1.56.0"`
+    pub apiversion: String,
+    pub mac: String,
+    pub ipaddress: String,
+    pub timezone: String,
+    /// The Zigbee channel the bridge communicates with the lights on, one of [`ZIGBEE_CHANNELS`]
+    pub zigbeechannel: u8,
+    pub modelid: String,
+    /// The software update status, missing on old bridges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swupdate2: Option<SoftwareUpdate>,
+    /// The users authorized to access the bridge, keyed by their name
+    #[serde(default)]
+    pub whitelist: HashMap<String, WhitelistEntry>,
+}
+
+impl BridgeConfig {
+    /// The parsed `apiversion`, or `None` if it isn't like `"1.56.0"`
+    pub fn api_version(&self) -> Option<ApiVersion> {
+        self.apiversion.parse().ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A version of the API of a bridge, which can be compared to check for features
+///
+/// Example:
+/// ```
+/// use hust::config::ApiVersion;
+///
+/// let version: ApiVersion = "1.56.0".parse().unwrap();
+/// assert!(version >= ApiVersion::new(1, 31, 0));
+/// assert!(version < "1.60.0".parse().unwrap());
+/// ```
+pub struct ApiVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl ApiVersion {
+    pub fn new(major: u16, minor: u16, patch: u16) -> Self {
+        ApiVersion { major, minor, patch }
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = crate::error::Error;
+
+    fn from_str(version: &str) -> crate::error::Result<ApiVersion> {
+        let invalid = || crate::error::Error::Arbitrary(format!("Invalid API version {}", version));
+        let mut parts = version.trim().split('.').map(|part| part.parse::<u16>().map_err(|_| invalid()));
+        let major = parts.next().ok_or_else(invalid)??;
+        let minor = parts.next().unwrap_or(Ok(0))?;
+        let patch = parts.next().unwrap_or(Ok(0))?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(ApiVersion::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A user authorized to access a bridge
+pub struct WhitelistEntry {
+    /// The device type the user was registered with
+    pub name: String,
+    #[serde(rename = "create date")]
+    pub create_date: String,
+    #[serde(rename = "last use date")]
+    pub last_use_date: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Software update status of a bridge and its devices
+pub struct SoftwareUpdate {
+    /// Tells wether the bridge currently checks for updates
+    pub checkforupdate: bool,
+    /// Summarized update state of the bridge and all its devices
+    pub state: SoftwareUpdateState,
+    /// Time of the last change in the update state
+    pub lastchange: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+/// State of a software update
+///
+/// States added by newer firmware are read as `Other`:
+/// ```
+/// use hust::config::SoftwareUpdateState;
+///
+/// let state: SoftwareUpdateState = serde_json::from_str(r#""installing""#).unwrap();
+/// assert_eq!(state, SoftwareUpdateState::Installing);
+/// let state: SoftwareUpdateState = serde_json::from_str(r#""rebooting""#).unwrap();
+/// assert_eq!(state, SoftwareUpdateState::Other);
+/// ```
+pub enum SoftwareUpdateState {
+    /// The bridge doesn't know wether updates are available
+    #[default]
+    Unknown,
+    /// Everything is up to date
+    NoUpdates,
+    /// An update is being downloaded
+    Transferring,
+    /// Updates for some devices are ready to install
+    AnyReadyToInstall,
+    /// Updates for all devices are ready to install
+    AllReadyToInstall,
+    /// An update is being installed
+    Installing,
+    /// Any state not known to this crate
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// How many resources of each kind a bridge can store
+///
+/// Missing kinds are reported as zero, which happens on old bridges.
+pub struct Capabilities {
+    #[serde(default)]
+    pub lights: Capacity,
+    #[serde(default)]
+    pub sensors: Capacity,
+    #[serde(default)]
+    pub groups: Capacity,
+    #[serde(default)]
+    pub scenes: Capacity,
+    #[serde(default)]
+    pub rules: Capacity,
+    #[serde(default)]
+    pub schedules: Capacity,
+    #[serde(default)]
+    pub resourcelinks: Capacity,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// How many resources of a kind a bridge can store
+pub struct Capacity {
+    /// How many more resources can be created
+    pub available: u32,
+    /// How many resources can be stored at most
+    pub total: u32,
+}
@@ -1,38 +1,241 @@
-use crate::bridge::Bridge;
-use crate::error::Result;
-use std::collections::HashSet;
-use std::io::{Error, ErrorKind};
-use std::net::UdpSocket;
+use crate::bridge::{bridge_id_of, Bridge, DEFAULT_USER_AGENT};
+use crate::error::{Error, Result};
+use socket2::SockRef;
+use std::collections::{HashSet, VecDeque};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
-/// SSDP service discovery request
-const DISCOVERY_TEXT: &[u8] = b"M-SEARCH * HTTP/1.1
-HOST: 239.255.255.250:1900
-MAN: ssdp:discover
-MX: 10
-ST: ssdp:all
+/// SSDP service discovery request for `host`, answered within `mx` seconds
+fn discovery_text(host: &str, mx: u8) -> Vec<u8> {
+    format!("M-SEARCH * HTTP/1.1\nHOST: {}\nMAN: ssdp:discover\nMX: {}\nST: ssdp:all\n\n", host, mx).into_bytes()
+}
+
+/// Default for the number of seconds the bridges may wait before they answer
+const DEFAULT_MX: u8 = 10;
+
+/// SSDP multicast address the discovery text is sent to
+const DISCOVERY_ADDR: &str = "239.255.255.250:1900";
+
+/// Link-local IPv6 SSDP multicast address the discovery text is sent to
+const DISCOVERY_ADDR_V6: &str = "[ff02::c]:1900";
+
+/// Host header of the discovery text for IPv6
+const DISCOVERY_HOST_V6: &str = "[FF02::C]:1900";
+
+/// Size of the buffer for answers, which fits any UDP datagram so no answer gets truncated
+const MAX_ANSWER_SIZE: usize = 65536;
 
-";
+/// How long to wait for an answer on one socket before looking at the next one
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
 
-/// Receives one bridge URL.
+/// The IP versions to search for bridges with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IpFamily {
+    /// Only IPv4, via `239.255.255.250`
+    V4,
+    /// Only IPv6, via the link-local group `ff02::c`
+    V6,
+    /// Both IPv4 and IPv6
+    ///
+    /// IPv6 is only used if it is available, so this also works in
+    /// networks without IPv6.
+    #[default]
+    Both,
+}
+
+/// Settings for the sockets the discovery text is sent on
+/// 
+/// Example:
+/// ```
+/// use std::net::Ipv4Addr;
+/// use std::time::Duration;
+/// use hust::{BridgeFinder, DiscoveryOptions};
+/// 
+/// let options = DiscoveryOptions::new()
+///     .set_multicast_ttl(2)
+///     .set_multicast_if_v4(Ipv4Addr::LOCALHOST);
+/// let finder = BridgeFinder::with_options(Duration::from_millis(100), &options).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryOptions {
+    /// The IP versions to search with
+    pub family: IpFamily,
+    /// How many routers the discovery text may pass, if not the default of 1
+    pub multicast_ttl: Option<u32>,
+    /// The address of the interface to send the IPv4 discovery text from
+    pub multicast_if_v4: Option<Ipv4Addr>,
+    /// The number of seconds the bridges may wait before they answer, if not 10
+    pub mx: Option<u8>,
+    /// Tells wether to ask the Hue cloud service if the discovery text can't be sent
+    pub cloud_fallback: bool,
+}
+
+impl DiscoveryOptions {
+    /// Creates the default options, which search with both IP versions in the local network
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Search using only the IP versions in `family`
+    pub fn family(mut self, family: IpFamily) -> Self {
+        self.family = family;
+        self
+    }
+
+    /// Let the discovery text pass up to `ttl` routers
+    /// 
+    /// By default, the discovery text doesn't leave the local network
+    /// segment, which is enough in most homes. Raising the TTL lets it
+    /// reach bridges behind routers, but also lets devices in networks
+    /// beyond the own one learn that a Hue client is searching, and
+    /// lets their answers tell which bridges exist there. Only raise it
+    /// as much as needed, and only in networks you trust.
+    /// 
+    /// For IPv6, this sets the hop limit.
+    pub fn set_multicast_ttl(mut self, ttl: u32) -> Self {
+        self.multicast_ttl = Some(ttl);
+        self
+    }
+
+    /// Let the bridges wait up to `mx` seconds before they answer
+    /// 
+    /// Devices answer after a random delay within this time, so on busy
+    /// networks a longer time spreads their answers. With a short time,
+    /// bridges answer sooner. Values below 1 are raised to 1.
+    pub fn mx(mut self, mx: u8) -> Self {
+        self.mx = Some(mx.max(1));
+        self
+    }
+
+    /// Ask the Hue cloud service for the bridges if the discovery text can't be sent
+    /// 
+    /// On some hosts, e.g. locked-down servers, sending multicast is
+    /// blocked. With this, the bridges are then asked for at
+    /// [`find_bridges_cloud`] instead of failing. This sends the public
+    /// IP address of the host to the service.
+    pub fn cloud_fallback(mut self, cloud_fallback: bool) -> Self {
+        self.cloud_fallback = cloud_fallback;
+        self
+    }
+
+    /// Send the IPv4 discovery text via the interface with the address `interface`
+    /// 
+    /// Unlike [`BridgeFinder::new_on_interface`], answers are still
+    /// received on all interfaces.
+    pub fn set_multicast_if_v4(mut self, interface: Ipv4Addr) -> Self {
+        self.multicast_if_v4 = Some(interface);
+        self
+    }
+}
+
+/// Sends the discovery text for the IP version of `socket`.
+fn send_discovery_text(socket: &UdpSocket, mx: u8) -> std::io::Result<()> {
+    if socket.local_addr()?.is_ipv6() {
+        socket.send_to(&discovery_text(DISCOVERY_HOST_V6, mx), DISCOVERY_ADDR_V6)?;
+    } else {
+        socket.send_to(&discovery_text(DISCOVERY_ADDR, mx), DISCOVERY_ADDR)?;
+    }
+    Ok(())
+}
+
+/// Creates a socket bound to `local_addr` to send the discovery text on.
+fn discovery_socket(local_addr: IpAddr, options: &DiscoveryOptions) -> std::io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(SocketAddr::new(local_addr, 0))?;
+    let socket_ref = SockRef::from(&socket);
+    match local_addr {
+        IpAddr::V4(_) => {
+            if let Some(ttl) = options.multicast_ttl {
+                socket_ref.set_multicast_ttl_v4(ttl)?;
+            }
+            if let Some(interface) = options.multicast_if_v4 {
+                socket_ref.set_multicast_if_v4(&interface)?;
+            }
+        }
+        IpAddr::V6(_) => {
+            if let Some(hops) = options.multicast_ttl {
+                socket_ref.set_multicast_hops_v6(hops)?;
+            }
+        }
+    }
+    Ok(socket)
+}
+
+/// Interval after which the discovery text is sent again, since UDP is lossy
+const RESEND_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long fetching the description of a found bridge may take
+const DESCRIPTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The relevant parts of an SSDP answer of a bridge
+struct Answer {
+    /// URL of the description of the bridge
+    location: String,
+    /// Identifier of the bridge, if it could be determined
+    bridge_id: Option<String>,
+}
+
+/// Receives one answer.
 /// 
 /// Before, the discovery text should have been sent on the socket via multicast.
-fn receive_answer(socket: &UdpSocket) -> std::io::Result<String> {
-    let mut buf = [0; 8192];
-    let (answer_size, _) = socket.recv_from(&mut buf)?;
-    let answer = String::from_utf8_lossy(&buf[0..answer_size]);
+/// Answers of other devices than Hue bridges result in `None`.
+/// `buf` must be large enough for any answer.
+fn receive_answer(socket: &UdpSocket, buf: &mut [u8]) -> std::io::Result<Option<Answer>> {
+    let (answer_size, _) = socket.recv_from(buf)?;
+    Ok(parse_answer(&buf[0..answer_size]))
+}
+
+/// Extracts the relevant parts of an SSDP answer.
+/// 
+/// Returns `None` if the answer isn't from a Hue bridge, which is
+/// recognized by the `hue-bridgeid` header or `IpBridge` in the server header.
+fn parse_answer(answer: &[u8]) -> Option<Answer> {
+    let answer = String::from_utf8_lossy(answer);
     let mut answer_lines = answer.lines();
-    if let Some(firstline) = answer_lines.next() {
-        if !firstline.starts_with("HTTP/1.1 200 OK") {
-            return Err(Error::from(ErrorKind::InvalidData))?;
-        }
-        for line in answer_lines {
-            if let Some(url) = line.strip_prefix("LOCATION: ") {
-                return Ok(String::from(url));
+    if !answer_lines.next()?.starts_with("HTTP/1.1 200 OK") {
+        return None;
+    }
+    let mut location = None;
+    let mut bridge_id = None;
+    let mut usn_id = None;
+    let mut is_bridge = false;
+    for line in answer_lines {
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "location" => location = Some(String::from(value)),
+                "hue-bridgeid" => {
+                    is_bridge = true;
+                    bridge_id = Some(value.to_ascii_uppercase());
+                }
+                "server" if value.contains("IpBridge") => is_bridge = true,
+                "usn" => usn_id = bridge_id_from_usn(value),
+                _ => {}
             }
         }
     }
-    Err(Error::from(ErrorKind::InvalidData))?
+    if !is_bridge {
+        return None;
+    }
+    Some(Answer {
+        location: location?,
+        bridge_id: bridge_id.or(usn_id),
+    })
+}
+
+/// Derives the bridge id from a USN like `uuid:2f402f80-da50-11e1-9b23-001788102201::upnp:rootdevice`.
+/// 
+/// Older bridges don't send the `hue-bridgeid` header, but their UUID ends
+/// with their MAC address, from which the id is formed by inserting `FFFE`.
+fn bridge_id_from_usn(usn: &str) -> Option<String> {
+    let uuid = usn.strip_prefix("uuid:")?.split("::").next()?;
+    let mac = uuid.rsplit('-').next()?;
+    if mac.len() != 12 || !mac.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(bridge_id_of(mac))
 }
 
 
@@ -40,25 +243,269 @@ fn receive_answer(socket: &UdpSocket) -> std::io::Result<String> {
 pub struct BridgeFinder {
     /// Birthday of the bridge finder, needed for timeout
     pub start: Instant,
-    /// The socket on which the responses are exepected
-    pub socket: UdpSocket,
-    /// Timeout after which the iteration will end
+    /// The sockets on which the responses are exepected, one per IP version
+    sockets: Vec<UdpSocket>,
+    /// Timeout after which the iteration will end, once the descriptions
+    /// being fetched arrived or took longer than 5 seconds
     pub timeout: Duration,
     /// Enables deduplication of the received URLs
     pub seen_urls: HashSet<String>,
+    /// When the discovery text will be sent again
+    pub next_send: Instant,
+    /// Number of bridges after which the iteration will end, even before the timeout
+    pub expected_count: Option<usize>,
+    /// The number of seconds the bridges may wait before they answer
+    pub mx: u8,
+    /// Bridges found by other means than SSDP, yielded first
+    fallback: VecDeque<Result<Bridge>>,
+    /// Number of descriptions which are being fetched
+    pending_descriptions: usize,
+    /// Number of bridges yielded so far, which `expected_count` is compared to
+    yielded: usize,
+    /// Receives the answers, kept to avoid allocating it for each one
+    buffer: Vec<u8>,
+    descriptions_sender: Sender<Result<Bridge>>,
+    /// Receives the bridges whose descriptions were fetched
+    descriptions: Receiver<Result<Bridge>>,
 }
 
 impl BridgeFinder {
     pub fn new(timeout: Duration) -> std::io::Result<Self> {
+        BridgeFinder::new_with_family(timeout, IpFamily::default())
+    }
+
+    /// Searches for bridges using only the IP versions in `family`.
+    pub fn new_with_family(timeout: Duration, family: IpFamily) -> std::io::Result<Self> {
+        BridgeFinder::with_options(timeout, &DiscoveryOptions::new().family(family))
+    }
+
+    /// Searches for bridges with the given settings for the sockets.
+    /// 
+    /// If the discovery text can't be sent with any IP version, the error
+    /// is returned, unless the cloud fallback is enabled. Then, the
+    /// iterator yields the bridges listed by the Hue cloud service instead.
+    /// Since these bridges don't answer via SSDP, [`BridgeFinder::ids`]
+    /// doesn't yield them.
+    pub fn with_options(timeout: Duration, options: &DiscoveryOptions) -> std::io::Result<Self> {
+        let v4 = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        let v6 = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+        let mut sockets = match options.family {
+            IpFamily::V4 => vec![discovery_socket(v4, options)?],
+            IpFamily::V6 => vec![discovery_socket(v6, options)?],
+            IpFamily::Both => {
+                let mut sockets = vec![discovery_socket(v4, options)?];
+                sockets.extend(discovery_socket(v6, options).ok());
+                sockets
+            }
+        };
+        let mx = options.mx.unwrap_or(DEFAULT_MX);
+        let mut send_error = None;
+        sockets.retain(|socket| match send_discovery_text(socket, mx) {
+            Ok(()) => true,
+            Err(e) => {
+                send_error = Some(e);
+                false
+            }
+        });
+        let mut finder = BridgeFinder::with_sockets(timeout, sockets, mx);
+        if let Some(e) = send_error {
+            if finder.sockets.is_empty() {
+                if !options.cloud_fallback {
+                    return Err(e);
+                }
+                finder.fallback = match find_bridges_cloud() {
+                    Ok(bridges) => bridges.into_iter().collect(),
+                    Err(e) => VecDeque::from(vec![Err(e)]),
+                };
+            }
+        }
+        Ok(finder)
+    }
+
+    fn with_sockets(timeout: Duration, sockets: Vec<UdpSocket>, mx: u8) -> Self {
         let start = Instant::now();
-        let socket = UdpSocket::bind("0.0.0.0:0")?;
-        socket.send_to(DISCOVERY_TEXT, "239.255.255.250:1900")?;
-        Ok(BridgeFinder {
+        let (descriptions_sender, descriptions) = channel();
+        BridgeFinder {
             start,
-            socket,
+            sockets,
             timeout,
             seen_urls: HashSet::new(),
-        })
+            next_send: start + RESEND_INTERVAL,
+            expected_count: None,
+            mx,
+            fallback: VecDeque::new(),
+            pending_descriptions: 0,
+            yielded: 0,
+            buffer: vec![0; MAX_ANSWER_SIZE],
+            descriptions_sender,
+            descriptions,
+        }
+    }
+
+    /// End the iteration as soon as `count` distinct bridges have been yielded.
+    /// 
+    /// Answers whose description can't be fetched don't count.
+    /// 
+    /// Without this, the iteration always lasts until the timeout, since
+    /// there's no way to know whether more bridges will answer.
+    /// 
+    /// Example:
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use hust::BridgeFinder;
+    /// 
+    /// // Most homes have a single bridge, which usually answers within a second
+    /// let mut finder = BridgeFinder::new(Duration::from_secs(5)).unwrap().with_expected_count(1);
+    /// if let Some(bridge) = finder.next() {
+    ///     println!("{}", bridge.unwrap());
+    /// }
+    /// ```
+    pub fn with_expected_count(mut self, count: usize) -> Self {
+        self.expected_count = Some(count);
+        self
+    }
+
+    /// Searches for bridges via the network interface with the address `local_addr`.
+    /// 
+    /// `local_addr` has to be the address of a local interface. On
+    /// machines with several interfaces, e.g. for Docker or a VPN, this
+    /// makes sure the search is sent into the network the bridges are in.
+    /// Only the IP version of `local_addr` is used.
+    pub fn new_on_interface(timeout: Duration, local_addr: IpAddr) -> std::io::Result<Self> {
+        let socket = discovery_socket(local_addr, &DiscoveryOptions::new())?;
+        send_discovery_text(&socket, DEFAULT_MX)?;
+        Ok(BridgeFinder::with_sockets(timeout, vec![socket], DEFAULT_MX))
+    }
+
+    /// Sends the discovery text again if it is due.
+    /// 
+    /// Like when the finder is created, a socket on which sending fails is
+    /// dropped, e.g. if its IP version isn't available anymore, while the
+    /// search goes on with the others. The error is only returned if
+    /// sending fails on every socket, which are then kept to try again.
+    /// Either way, the next attempt is made after `RESEND_INTERVAL`, so a
+    /// network which is down for a moment doesn't result in an error on
+    /// each poll.
+    fn resend_if_due(&mut self) -> std::io::Result<()> {
+        if Instant::now() < self.next_send {
+            return Ok(());
+        }
+        self.next_send = Instant::now() + RESEND_INTERVAL;
+        let sent: Vec<std::io::Result<()>> = self.sockets.iter().map(|socket| send_discovery_text(socket, self.mx)).collect();
+        if sent.iter().all(|result| result.is_err()) {
+            return match sent.into_iter().next() {
+                Some(Err(e)) => Err(e),
+                _ => Ok(()),
+            };
+        }
+        let mut sent = sent.into_iter();
+        self.sockets.retain(|_| matches!(sent.next(), Some(Ok(()))));
+        Ok(())
+    }
+
+    /// Receives the next answer of a bridge not seen before.
+    fn next_answer(&mut self) -> Option<std::io::Result<Answer>> {
+        loop {
+            match self.poll_answer(Duration::MAX)? {
+                Ok(Some(answer)) => return Some(Ok(answer)),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Tells wether as many bridges as expected were yielded.
+    fn found_expected(&self) -> bool {
+        matches!(self.expected_count, Some(count) if self.yielded >= count)
+    }
+
+    /// Counts `item` towards the expected count if it is a bridge, and passes it on.
+    fn count_yielded<T>(&mut self, item: Option<Result<T>>) -> Option<Result<T>> {
+        if let Some(Ok(_)) = item {
+            self.yielded += 1;
+        }
+        item
+    }
+
+    /// Waits at most `max_wait` for an answer of a bridge not seen before.
+    /// 
+    /// Returns `None` when the search is over, and `Some(Ok(None))` if
+    /// no new answer arrived in time.
+    fn poll_answer(&mut self, max_wait: Duration) -> Option<std::io::Result<Option<Answer>>> {
+        let time_spent = self.start.elapsed();
+        if time_spent > self.timeout || self.sockets.is_empty() {
+            return None;
+        }
+        if self.found_expected() {
+            return None;
+        }
+        if let Err(e) = self.resend_if_due() {
+            return Some(Err(e));
+        }
+        let until_resend = self.next_send.saturating_duration_since(Instant::now());
+        let mut read_timeout = (self.timeout - time_spent).min(until_resend).min(max_wait);
+        if self.sockets.len() > 1 {
+            // Don't let one socket keep the answers on the others waiting
+            read_timeout = read_timeout.min(POLL_INTERVAL);
+        }
+        for socket in &self.sockets {
+            if let Err(e) = socket.set_read_timeout(Some(read_timeout.max(Duration::from_millis(1)))) {
+                return Some(Err(e));
+            }
+            match receive_answer(socket, &mut self.buffer) {
+                Ok(None) => {}
+                Ok(Some(answer)) => {
+                    if !self.seen_urls.contains(&answer.location) {
+                        self.seen_urls.insert(answer.location.clone());
+                        return Some(Ok(Some(answer)));
+                    }
+                }
+                Err(e) => {
+                    if e.kind() != ErrorKind::WouldBlock && e.kind() != ErrorKind::TimedOut {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+        Some(Ok(None))
+    }
+
+    /// Fetches the description of a bridge in the background.
+    /// 
+    /// This way, answers of other bridges are received in the meantime
+    /// instead of being missed. Fetching may take `DESCRIPTION_TIMEOUT`.
+    fn fetch_description(&mut self, location: String) {
+        let sender = self.descriptions_sender.clone();
+        self.pending_descriptions += 1;
+        std::thread::spawn(move || {
+            // The receiver is gone if the finder was dropped, then nobody needs the bridge
+            let _ = sender.send(Bridge::from_description_url_within(&location, DESCRIPTION_TIMEOUT));
+        });
+    }
+
+    /// The sockets on which the answers of the bridges are received.
+    /// 
+    /// There is one socket per IP version searched, which may be none if
+    /// no discovery text could be sent, e.g. without network.
+    pub fn sockets(&self) -> &[UdpSocket] {
+        &self.sockets
+    }
+
+    /// Yield the ids and description URLs of the bridges instead of fetching their descriptions.
+    /// 
+    /// This saves an HTTP request per bridge, e.g. to skip bridges whose
+    /// credentials are already known. A full `Bridge` can be created from
+    /// the description URL with `Bridge::from_description_url`.
+    pub fn ids(self) -> BridgeIds {
+        BridgeIds(self)
+    }
+
+    /// Yield every bridge as soon as it answers, without fetching its description.
+    /// 
+    /// This allows showing a found bridge right away, while its description
+    /// is only loaded once `DiscoveredBridge::resolve` is called.
+    pub fn discovered(self) -> DiscoveredBridges {
+        DiscoveredBridges(self)
     }
 }
 
@@ -66,38 +513,103 @@ impl Iterator for BridgeFinder {
     type Item = Result<Bridge>;
 
     fn next(&mut self) -> Option<Result<Bridge>> {
-        let time_spent = self.start.elapsed();
-        if time_spent > self.timeout {
+        if self.found_expected() {
             return None;
         }
-        if let Err(e) = self
-            .socket
-            .set_read_timeout(Some(self.timeout - time_spent))
-        {
-            return Some(Err(e.into()));
-        }
-        match receive_answer(&self.socket) {
-            Ok(url) => {
-                if self.seen_urls.contains(&url) {
-                    self.next()
-                } else {
-                    self.seen_urls.insert(url.clone());
-                    Some(Bridge::from_description_url(url))
-                }
+        let bridge = self.next_bridge();
+        self.count_yielded(bridge)
+    }
+}
+
+impl BridgeFinder {
+    /// Yields the next bridge, whose description is fetched in the background.
+    fn next_bridge(&mut self) -> Option<Result<Bridge>> {
+        if let Some(bridge) = self.fallback.pop_front() {
+            return Some(bridge);
+        }
+        loop {
+            if let Ok(bridge) = self.descriptions.try_recv() {
+                self.pending_descriptions -= 1;
+                return Some(bridge);
             }
-            Err(e) => {
-                if e.kind() == ErrorKind::WouldBlock {
-                    self.next()
-                } else {
-                    Some(Err(e.into()))
+            // Look for fetched descriptions regularly while waiting for answers
+            let max_wait = if self.pending_descriptions > 0 { POLL_INTERVAL } else { Duration::MAX };
+            match self.poll_answer(max_wait) {
+                Some(Ok(Some(answer))) => self.fetch_description(answer.location),
+                Some(Ok(None)) => {}
+                Some(Err(e)) => return Some(Err(e.into())),
+                None if self.pending_descriptions > 0 => {
+                    self.pending_descriptions -= 1;
+                    // Fetches started before the search ended, so they end in time unless they hang
+                    let deadline = self.start + self.timeout + DESCRIPTION_TIMEOUT;
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    return Some(self.descriptions.recv_timeout(remaining).unwrap_or(Err(Error::Timeout)));
                 }
+                None => return None,
+            }
+        }
+    }
+}
+
+/// An iterator over the ids and description URLs of the bridges in this network
+/// 
+/// Bridges whose id can't be determined from their answer are skipped.
+pub struct BridgeIds(BridgeFinder);
+
+impl Iterator for BridgeIds {
+    type Item = Result<(String, String)>;
+
+    fn next(&mut self) -> Option<Result<(String, String)>> {
+        loop {
+            if self.0.found_expected() {
+                return None;
+            }
+            match self.0.next_answer()? {
+                Ok(Answer { location, bridge_id: Some(id) }) => return self.0.count_yielded(Some(Ok((id, location)))),
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
             }
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A bridge that answered the discovery, but whose description hasn't been fetched yet
+pub struct DiscoveredBridge {
+    /// Identifier of the bridge, if it could be determined from its answer
+    pub id: Option<String>,
+    /// URL of the description of the bridge
+    pub location: String,
+}
+
+impl DiscoveredBridge {
+    /// Fetch the description of the bridge.
+    pub fn resolve(&self) -> Result<Bridge> {
+        Bridge::from_description_url(self.location.clone())
+    }
+}
+
+/// An iterator over the bridges in this network, yielded as soon as they answer
+pub struct DiscoveredBridges(BridgeFinder);
+
+impl Iterator for DiscoveredBridges {
+    type Item = Result<DiscoveredBridge>;
+
+    fn next(&mut self) -> Option<Result<DiscoveredBridge>> {
+        if self.0.found_expected() {
+            return None;
+        }
+        match self.0.next_answer()? {
+            Ok(Answer { location, bridge_id }) => self.0.count_yielded(Some(Ok(DiscoveredBridge { id: bridge_id, location }))),
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
+
 /// Yield all Hue bridges you can find in the network within `timeout`.
 /// 
+/// Both IPv4 and, where available, IPv6 are used for the search.
+/// 
 /// Example:
 /// ```
 /// use std::time::Duration;
@@ -113,3 +625,141 @@ impl Iterator for BridgeFinder {
 pub fn find_bridges(timeout: Duration) -> std::io::Result<impl Iterator<Item=Result<Bridge>>> {
     BridgeFinder::new(timeout)
 }
+
+/// Yield all Hue bridges you can find within `timeout` via the interface with the address `local_addr`.
+/// 
+/// See [`BridgeFinder::new_on_interface`].
+pub fn find_bridges_on(timeout: Duration, local_addr: IpAddr) -> std::io::Result<impl Iterator<Item=Result<Bridge>>> {
+    BridgeFinder::new_on_interface(timeout, local_addr)
+}
+
+/// Yield all Hue bridges you can find within `timeout` using only the IP versions in `family`.
+pub fn find_bridges_with_family(timeout: Duration, family: IpFamily) -> std::io::Result<impl Iterator<Item=Result<Bridge>>> {
+    BridgeFinder::new_with_family(timeout, family)
+}
+
+/// Search for Hue bridges within `timeout` on a background thread.
+/// 
+/// The bridges are sent through the returned channel as soon as they are
+/// found, so e.g. a user interface can list them while the search goes on.
+/// After the receiver is dropped, the search stops as soon as the next
+/// bridge is found instead of lasting until the timeout. If the search can't be
+/// started, the error is sent through the channel instead.
+/// 
+/// Example:
+/// ```
+/// use std::time::Duration;
+/// use hust::find_bridges_channel;
+/// 
+/// let (search, bridges) = find_bridges_channel(Duration::from_secs(2));
+/// for bridge in bridges {
+///     println!("{:?}", bridge);
+/// }
+/// search.join().unwrap();
+/// ```
+pub fn find_bridges_channel(timeout: Duration) -> (JoinHandle<()>, Receiver<Result<Bridge>>) {
+    let (sender, receiver) = channel();
+    let search = std::thread::spawn(move || {
+        let finder = match BridgeFinder::new(timeout) {
+            Ok(finder) => finder,
+            Err(e) => {
+                let _ = sender.send(Err(e.into()));
+                return;
+            }
+        };
+        for bridge in finder {
+            if sender.send(bridge).is_err() {
+                // Nobody is interested in further bridges
+                return;
+            }
+        }
+    });
+    (search, receiver)
+}
+
+/// URL of the Hue N-UPnP discovery service
+const CLOUD_DISCOVERY_URL: &str = "https://discovery.meethue.com/";
+
+/// A bridge as listed by the N-UPnP discovery service
+#[derive(Deserialize, Debug)]
+struct CloudBridge {
+    internalipaddress: String,
+}
+
+/// Find the Hue bridges in this network via the Hue N-UPnP cloud service.
+/// 
+/// The bridges register their internal address at `discovery.meethue.com`,
+/// which lists the bridges behind the same public IP. This works on
+/// networks where SSDP multicast doesn't, e.g. in VPNs or containers.
+/// 
+/// The service may list bridges which have moved or are offline, so
+/// there is a result per listed bridge. An error is only returned if the
+/// service itself can't be asked, e.g. because it limits the requests
+/// with `429 Too Many Requests`.
+pub fn find_bridges_cloud() -> Result<Vec<Result<Bridge>>> {
+    let client = reqwest::blocking::Client::builder().user_agent(DEFAULT_USER_AGENT).build()?;
+    let cloud_bridges: Vec<CloudBridge> = client.get(CLOUD_DISCOVERY_URL).send()?.error_for_status()?.json()?;
+    Ok(cloud_bridges
+        .into_iter()
+        .map(|cloud_bridge| Bridge::from_ip(&cloud_bridge.internalipaddress))
+        .collect())
+}
+
+/// Collect all Hue bridges you can find in the network within `timeout`, without blocking.
+/// 
+/// This is the async counterpart of [`find_bridges`].
+#[cfg(feature = "async")]
+pub async fn find_bridges_async(timeout: Duration) -> std::io::Result<Vec<Result<Bridge>>> {
+    let start = Instant::now();
+    let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+    socket.send_to(&discovery_text(DISCOVERY_ADDR, DEFAULT_MX), DISCOVERY_ADDR).await?;
+    let mut seen_urls = HashSet::new();
+    let mut bridges = vec![];
+    let mut buf = vec![0; MAX_ANSWER_SIZE];
+    loop {
+        let time_spent = start.elapsed();
+        if time_spent > timeout {
+            break;
+        }
+        let answer_size = match tokio::time::timeout(timeout - time_spent, socket.recv_from(&mut buf)).await {
+            Ok(received) => received?.0,
+            Err(_) => break,
+        };
+        if let Some(answer) = parse_answer(&buf[0..answer_size]) {
+            if seen_urls.insert(answer.location.clone()) {
+                bridges.push(Bridge::from_description_url_async(answer.location).await);
+            }
+        }
+    }
+    Ok(bridges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The answer of a bridge to the discovery text
+    fn answer(id: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nLOCATION: http://192.168.1.{}:80/description.xml\r\nhue-bridgeid: 001788FFFE10220{}\r\n\r\n",
+            id, id
+        )
+    }
+
+    #[test]
+    fn search_ends_once_expected_count_is_yielded() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let bridges = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for id in ["1", "2"] {
+            bridges.send_to(answer(id).as_bytes(), socket.local_addr().unwrap()).unwrap();
+        }
+        let start = Instant::now();
+        let ids: Vec<_> = BridgeFinder::with_sockets(Duration::from_secs(10), vec![socket], DEFAULT_MX)
+            .with_expected_count(1)
+            .ids()
+            .collect();
+        assert_eq!(ids.len(), 1);
+        assert_eq!(ids[0].as_ref().unwrap().0, "001788FFFE102201");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,720 @@
+use crate::error::{Error, Result};
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey};
+use ring::digest;
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::io::ErrorKind;
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+/// DTLS 1.2, as it is written in records and hellos
+const VERSION: [u8; 2] = [0xfe, 0xfd];
+
+/// `TLS_PSK_WITH_AES_128_GCM_SHA256`, the only cipher suite bridges offer for streaming
+const CIPHER_SUITE: [u8; 2] = [0x00, 0xa8];
+
+/// Signals that the client supports secure renegotiation, see RFC 5746
+const EMPTY_RENEGOTIATION_INFO_SCSV: [u8; 2] = [0x00, 0xff];
+
+// Content types of records
+const CHANGE_CIPHER_SPEC: u8 = 20;
+const ALERT: u8 = 21;
+const HANDSHAKE: u8 = 22;
+const APPLICATION_DATA: u8 = 23;
+
+// Types of handshake messages
+const CLIENT_HELLO: u8 = 1;
+const SERVER_HELLO: u8 = 2;
+const HELLO_VERIFY_REQUEST: u8 = 3;
+const SERVER_HELLO_DONE: u8 = 14;
+const CLIENT_KEY_EXCHANGE: u8 = 16;
+const FINISHED: u8 = 20;
+
+// Alerts which tell that the server doesn't accept the key
+const BAD_RECORD_MAC: u8 = 20;
+const HANDSHAKE_FAILURE: u8 = 40;
+const DECRYPT_ERROR: u8 = 51;
+const UNKNOWN_PSK_IDENTITY: u8 = 115;
+
+/// Size of the header of a record
+const RECORD_HEADER_LEN: usize = 13;
+
+/// Size of the header of a handshake message
+const HANDSHAKE_HEADER_LEN: usize = 12;
+
+/// Size of the part of the nonce which is sent with each encrypted record
+const EXPLICIT_NONCE_LEN: usize = 8;
+
+/// Size of the authentication tag of an encrypted record
+const TAG_LEN: usize = 16;
+
+/// Size of the `verify_data` of Finished messages
+const VERIFY_DATA_LEN: usize = 12;
+
+/// How long to wait for the answer of the bridge before sending a flight again
+const RETRANSMIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often a flight is sent before the handshake is given up
+const MAX_TRANSMISSIONS: u32 = 5;
+
+/// Size of the buffer for received datagrams
+const MAX_DATAGRAM_SIZE: usize = 16384;
+
+/// A DTLS 1.2 connection, authenticated with a pre-shared key
+///
+/// This implements just what the Entertainment API needs: The handshake
+/// with the single cipher suite of the bridge, and sending application
+/// data. Handshake messages must not be fragmented, which the short ones
+/// of a PSK handshake never are. Records which can't be decrypted or
+/// parsed are dropped, as required by RFC 6347.
+pub(crate) struct DtlsClient {
+    socket: UdpSocket,
+    /// Sequence number of the next record, per epoch
+    record_sequences: [u64; 2],
+    /// `message_seq` of the next handshake message to send
+    message_sequence: u16,
+    /// `message_seq` of the next handshake message expected from the bridge
+    receive_sequence: u16,
+    /// All handshake messages so far, which the Finished messages verify
+    transcript: Vec<u8>,
+    /// Keys of the records we send in epoch 1
+    client_keys: Option<RecordKeys>,
+    /// Keys of the records the bridge sends in epoch 1
+    server_keys: Option<RecordKeys>,
+    /// Number of records received, including dropped ones
+    records_received: u64,
+}
+
+/// A record to send, which is encrypted in epoch 1
+struct Outgoing {
+    content_type: u8,
+    epoch: u16,
+    payload: Vec<u8>,
+}
+
+/// A handshake message received from the bridge
+struct Message {
+    msg_type: u8,
+    body: Vec<u8>,
+}
+
+/// The key and implicit nonce of the records of one direction
+struct RecordKeys {
+    key: LessSafeKey,
+    salt: [u8; 4],
+}
+
+impl RecordKeys {
+    fn new(key: &[u8], salt: &[u8]) -> RecordKeys {
+        let key = UnboundKey::new(&aead::AES_128_GCM, key).expect("AES-128 keys have 16 bytes");
+        let mut implicit = [0; 4];
+        implicit.copy_from_slice(salt);
+        RecordKeys {
+            key: LessSafeKey::new(key),
+            salt: implicit,
+        }
+    }
+
+    /// The nonce of a record, made of the salt and the explicit nonce
+    fn nonce(&self, explicit: &[u8]) -> Nonce {
+        let mut nonce = [0; 12];
+        nonce[..4].copy_from_slice(&self.salt);
+        nonce[4..].copy_from_slice(explicit);
+        Nonce::assume_unique_for_key(nonce)
+    }
+
+    /// Encrypts `plaintext`, returning the fragment of the record.
+    ///
+    /// The record number serves as explicit nonce, so each one is used once.
+    fn seal(&self, content_type: u8, record_number: u64, plaintext: &[u8]) -> Vec<u8> {
+        let explicit = record_number.to_be_bytes();
+        let mut fragment = explicit.to_vec();
+        let mut ciphertext = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                self.nonce(&explicit),
+                Aad::from(additional_data(content_type, record_number, plaintext.len())),
+                &mut ciphertext,
+            )
+            .expect("Records are far below the size limit of AES-GCM");
+        fragment.extend_from_slice(&ciphertext);
+        fragment
+    }
+
+    /// Decrypts the fragment of a record, if it is authentic.
+    fn open(&self, content_type: u8, record_number: u64, fragment: &[u8]) -> Option<Vec<u8>> {
+        if fragment.len() < EXPLICIT_NONCE_LEN + TAG_LEN {
+            return None;
+        }
+        let (explicit, ciphertext) = fragment.split_at(EXPLICIT_NONCE_LEN);
+        let plaintext_len = ciphertext.len() - TAG_LEN;
+        let mut plaintext = ciphertext.to_vec();
+        self.key
+            .open_in_place(
+                self.nonce(explicit),
+                Aad::from(additional_data(content_type, record_number, plaintext_len)),
+                &mut plaintext,
+            )
+            .ok()?;
+        plaintext.truncate(plaintext_len);
+        Some(plaintext)
+    }
+}
+
+impl DtlsClient {
+    /// Performs the handshake with the server `socket` is connected to.
+    ///
+    /// `identity` tells the server which `psk` to use. If the server
+    /// doesn't accept the key, `Error::ClientKeyRejected` is returned.
+    pub fn connect(socket: UdpSocket, identity: &[u8], psk: &[u8]) -> Result<DtlsClient> {
+        let mut client = DtlsClient::new(socket);
+        let rng = SystemRandom::new();
+        let mut client_random = [0; 32];
+        rng.fill(&mut client_random)
+            .map_err(|_| handshake_error("can't generate random numbers"))?;
+
+        // The server may ask to prove that we receive at our address by echoing a cookie
+        let hello = client.handshake_message(CLIENT_HELLO, &client_hello(&client_random, &[]));
+        let mut answer = client.exchange(&[hello], &[HELLO_VERIFY_REQUEST, SERVER_HELLO_DONE])?;
+        if answer[0].msg_type == HELLO_VERIFY_REQUEST {
+            let cookie = hello_verify_cookie(&answer[0].body)?;
+            // The first hello and the request are not part of the transcript
+            client.transcript.clear();
+            let hello = client.handshake_message(CLIENT_HELLO, &client_hello(&client_random, &cookie));
+            answer = client.exchange(&[hello], &[SERVER_HELLO_DONE])?;
+        }
+        let server_random = match answer.iter().find(|message| message.msg_type == SERVER_HELLO) {
+            Some(message) => server_hello_random(&message.body)?,
+            None => return Err(handshake_error("the bridge didn't send a hello")),
+        };
+
+        let master_secret = master_secret(psk, &client_random, &server_random);
+        let key_block = key_block(&master_secret, &client_random, &server_random);
+        client.client_keys = Some(RecordKeys::new(&key_block[..16], &key_block[32..36]));
+        client.server_keys = Some(RecordKeys::new(&key_block[16..32], &key_block[36..40]));
+
+        let mut key_exchange = (identity.len() as u16).to_be_bytes().to_vec();
+        key_exchange.extend_from_slice(identity);
+        let key_exchange = client.handshake_message(CLIENT_KEY_EXCHANGE, &key_exchange);
+        let change_cipher_spec = Outgoing {
+            content_type: CHANGE_CIPHER_SPEC,
+            epoch: 0,
+            payload: vec![1],
+        };
+        let verify_data = client.verify_data(&master_secret, b"client finished");
+        let mut finished = client.handshake_message(FINISHED, &verify_data);
+        finished.epoch = 1;
+        let answer = client.exchange(&[key_exchange, change_cipher_spec, finished], &[FINISHED])?;
+        let expected = prf(
+            &master_secret,
+            b"server finished",
+            &client.transcript_hash_without(&answer[answer.len() - 1]),
+            VERIFY_DATA_LEN,
+        );
+        if answer[answer.len() - 1].body != expected {
+            return Err(Error::ClientKeyRejected);
+        }
+        client.socket.set_read_timeout(None)?;
+        Ok(client)
+    }
+
+    /// A connection on `socket` before the handshake
+    fn new(socket: UdpSocket) -> DtlsClient {
+        DtlsClient {
+            socket,
+            record_sequences: [0; 2],
+            message_sequence: 0,
+            receive_sequence: 0,
+            transcript: Vec::new(),
+            client_keys: None,
+            server_keys: None,
+            records_received: 0,
+        }
+    }
+
+    /// Sends `data` in a single encrypted record.
+    pub fn send(&mut self, data: &[u8]) -> Result<()> {
+        let record = self.record(&Outgoing {
+            content_type: APPLICATION_DATA,
+            epoch: 1,
+            payload: data.to_vec(),
+        });
+        self.socket.send(&record)?;
+        Ok(())
+    }
+
+    /// Tells the server that no more data will be sent.
+    pub fn close(&mut self) -> Result<()> {
+        // A close_notify alert with the level warning
+        let record = self.record(&Outgoing {
+            content_type: ALERT,
+            epoch: 1,
+            payload: vec![1, 0],
+        });
+        self.socket.send(&record)?;
+        Ok(())
+    }
+
+    /// Wraps a handshake message into a record of epoch 0 and adds it to the transcript.
+    fn handshake_message(&mut self, msg_type: u8, body: &[u8]) -> Outgoing {
+        let length = u24(body.len());
+        let mut message = vec![msg_type];
+        message.extend_from_slice(&length);
+        message.extend_from_slice(&self.message_sequence.to_be_bytes());
+        // Sent in a single fragment
+        message.extend_from_slice(&[0, 0, 0]);
+        message.extend_from_slice(&length);
+        message.extend_from_slice(body);
+        self.message_sequence += 1;
+        self.transcript.extend_from_slice(&message);
+        Outgoing {
+            content_type: HANDSHAKE,
+            epoch: 0,
+            payload: message,
+        }
+    }
+
+    /// Serializes a record with the next sequence number of its epoch.
+    fn record(&mut self, outgoing: &Outgoing) -> Vec<u8> {
+        let epoch = usize::from(outgoing.epoch);
+        let record_number = u64::from(outgoing.epoch) << 48 | self.record_sequences[epoch];
+        self.record_sequences[epoch] += 1;
+        let fragment = match (&self.client_keys, outgoing.epoch) {
+            (Some(keys), 1) => keys.seal(outgoing.content_type, record_number, &outgoing.payload),
+            _ => outgoing.payload.clone(),
+        };
+        let mut record = vec![outgoing.content_type];
+        record.extend_from_slice(&VERSION);
+        record.extend_from_slice(&record_number.to_be_bytes());
+        record.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+        record.extend_from_slice(&fragment);
+        record
+    }
+
+    /// Sends a flight and waits for the answering flight of the server.
+    ///
+    /// The answer is complete with a handshake message of one of the `last`
+    /// types. If it doesn't arrive in time, the flight is sent again.
+    fn exchange(&mut self, flight: &[Outgoing], last: &[u8]) -> Result<Vec<Message>> {
+        let mut answer = Vec::new();
+        let mut buffer = vec![0; MAX_DATAGRAM_SIZE];
+        let records_before = self.records_received;
+        for _ in 0..MAX_TRANSMISSIONS {
+            let datagram: Vec<u8> = flight.iter().flat_map(|outgoing| self.record(outgoing)).collect();
+            self.socket.send(&datagram)?;
+            // Datagrams which are dropped mustn't put off the retransmission
+            let deadline = Instant::now() + RETRANSMIT_TIMEOUT;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                self.socket.set_read_timeout(Some(remaining))?;
+                let size = match self.socket.recv(&mut buffer) {
+                    Ok(size) => size,
+                    Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+                    Err(e) => return Err(e.into()),
+                };
+                for message in self.receive(&buffer[..size])? {
+                    let is_last = last.contains(&message.msg_type);
+                    answer.push(message);
+                    if is_last {
+                        return Ok(answer);
+                    }
+                }
+            }
+        }
+        if self.records_received == records_before {
+            Err(handshake_error("the bridge doesn't answer"))
+        } else if self.client_keys.is_some() {
+            // The bridge drops our Finished message if it can't decrypt it,
+            // and keeps sending its previous flight
+            Err(Error::ClientKeyRejected)
+        } else {
+            Err(handshake_error("the bridge doesn't complete its answer"))
+        }
+    }
+
+    /// Extracts the new handshake messages from a datagram.
+    ///
+    /// Retransmitted messages, records of other epochs and records which
+    /// can't be decrypted or parsed are skipped. Only fatal alerts are errors.
+    fn receive(&mut self, datagram: &[u8]) -> Result<Vec<Message>> {
+        let mut messages = Vec::new();
+        let mut rest = datagram;
+        while rest.len() >= RECORD_HEADER_LEN {
+            let content_type = rest[0];
+            let record_number = u64::from_be_bytes([rest[3], rest[4], rest[5], rest[6], rest[7], rest[8], rest[9], rest[10]]);
+            let length = usize::from(u16::from_be_bytes([rest[11], rest[12]]));
+            if rest.len() < RECORD_HEADER_LEN + length {
+                break;
+            }
+            let fragment = &rest[RECORD_HEADER_LEN..RECORD_HEADER_LEN + length];
+            rest = &rest[RECORD_HEADER_LEN + length..];
+            self.records_received += 1;
+            let plaintext = match (record_number >> 48, &self.server_keys) {
+                (0, _) => fragment.to_vec(),
+                (1, Some(keys)) => match keys.open(content_type, record_number, fragment) {
+                    Some(plaintext) => plaintext,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            match content_type {
+                HANDSHAKE => messages.extend(self.handshake_messages(&plaintext)),
+                // Fatal alerts end the handshake
+                ALERT if plaintext.len() == 2 && plaintext[0] == 2 => {
+                    return Err(match plaintext[1] {
+                        BAD_RECORD_MAC | HANDSHAKE_FAILURE | DECRYPT_ERROR | UNKNOWN_PSK_IDENTITY => {
+                            Error::ClientKeyRejected
+                        }
+                        alert => handshake_error(&format!("the bridge aborted with alert {}", alert)),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Splits the fragment of a handshake record into messages and adds the new ones to the transcript.
+    ///
+    /// Fragmented and truncated messages are skipped, with the rest of the record.
+    fn handshake_messages(&mut self, mut fragment: &[u8]) -> Vec<Message> {
+        let mut messages = Vec::new();
+        while fragment.len() >= HANDSHAKE_HEADER_LEN {
+            let length = from_u24(&fragment[1..4]);
+            let message_sequence = u16::from_be_bytes([fragment[4], fragment[5]]);
+            let fragment_offset = from_u24(&fragment[6..9]);
+            let fragment_length = from_u24(&fragment[9..12]);
+            if fragment_offset != 0 || fragment_length != length || fragment.len() < HANDSHAKE_HEADER_LEN + length {
+                break;
+            }
+            let (message, rest) = fragment.split_at(HANDSHAKE_HEADER_LEN + length);
+            fragment = rest;
+            if message_sequence != self.receive_sequence {
+                continue;
+            }
+            self.receive_sequence += 1;
+            self.transcript.extend_from_slice(message);
+            messages.push(Message {
+                msg_type: message[0],
+                body: message[HANDSHAKE_HEADER_LEN..].to_vec(),
+            });
+        }
+        messages
+    }
+
+    /// The `verify_data` of a Finished message, computed over the transcript so far
+    fn verify_data(&self, master_secret: &[u8], label: &[u8]) -> Vec<u8> {
+        let hash = digest::digest(&digest::SHA256, &self.transcript);
+        prf(master_secret, label, hash.as_ref(), VERIFY_DATA_LEN)
+    }
+
+    /// The hash of the transcript before the last message, which has to be `last`
+    fn transcript_hash_without(&self, last: &Message) -> Vec<u8> {
+        let end = self.transcript.len() - HANDSHAKE_HEADER_LEN - last.body.len();
+        digest::digest(&digest::SHA256, &self.transcript[..end]).as_ref().to_vec()
+    }
+}
+
+/// An error of the handshake, described by `reason`
+fn handshake_error(reason: &str) -> Error {
+    Error::Arbitrary(format!("DTLS handshake failed: {}", reason))
+}
+
+/// The data a record is authenticated with, besides its content
+fn additional_data(content_type: u8, record_number: u64, length: usize) -> [u8; 13] {
+    let mut data = [0; 13];
+    data[..8].copy_from_slice(&record_number.to_be_bytes());
+    data[8] = content_type;
+    data[9..11].copy_from_slice(&VERSION);
+    data[11..].copy_from_slice(&(length as u16).to_be_bytes());
+    data
+}
+
+/// The body of a ClientHello
+fn client_hello(random: &[u8; 32], cookie: &[u8]) -> Vec<u8> {
+    let mut hello = VERSION.to_vec();
+    hello.extend_from_slice(random);
+    // No session to resume
+    hello.push(0);
+    hello.push(cookie.len() as u8);
+    hello.extend_from_slice(cookie);
+    hello.extend_from_slice(&4u16.to_be_bytes());
+    hello.extend_from_slice(&CIPHER_SUITE);
+    hello.extend_from_slice(&EMPTY_RENEGOTIATION_INFO_SCSV);
+    // Only the null compression
+    hello.extend_from_slice(&[1, 0]);
+    hello
+}
+
+/// The cookie of a HelloVerifyRequest
+fn hello_verify_cookie(body: &[u8]) -> Result<Vec<u8>> {
+    let length = usize::from(*body.get(2).ok_or_else(|| handshake_error("invalid cookie"))?);
+    match body.get(3..3 + length) {
+        Some(cookie) => Ok(cookie.to_vec()),
+        None => Err(handshake_error("invalid cookie")),
+    }
+}
+
+/// The random of a ServerHello, after checking the chosen cipher suite
+fn server_hello_random(body: &[u8]) -> Result<[u8; 32]> {
+    let session_id_len = usize::from(*body.get(34).ok_or_else(|| handshake_error("invalid hello"))?);
+    let cipher_suite = body.get(35 + session_id_len..37 + session_id_len);
+    if cipher_suite != Some(&CIPHER_SUITE[..]) {
+        return Err(handshake_error("the bridge chose an unsupported cipher suite"));
+    }
+    let mut random = [0; 32];
+    random.copy_from_slice(&body[2..34]);
+    Ok(random)
+}
+
+/// The premaster secret of a plain PSK key exchange, see RFC 4279
+fn psk_premaster_secret(psk: &[u8]) -> Vec<u8> {
+    let length = (psk.len() as u16).to_be_bytes();
+    let mut secret = length.to_vec();
+    secret.resize(2 + psk.len(), 0);
+    secret.extend_from_slice(&length);
+    secret.extend_from_slice(psk);
+    secret
+}
+
+/// The master secret of a connection, derived from the pre-shared key
+fn master_secret(psk: &[u8], client_random: &[u8; 32], server_random: &[u8; 32]) -> Vec<u8> {
+    prf(
+        &psk_premaster_secret(psk),
+        b"master secret",
+        &[&client_random[..], &server_random[..]].concat(),
+        48,
+    )
+}
+
+/// The keys and implicit nonces of both directions, in the order client key, server key, client salt and server salt
+fn key_block(master_secret: &[u8], client_random: &[u8; 32], server_random: &[u8; 32]) -> Vec<u8> {
+    prf(
+        master_secret,
+        b"key expansion",
+        &[&server_random[..], &client_random[..]].concat(),
+        40,
+    )
+}
+
+/// The pseudorandom function of TLS 1.2 with SHA-256, see RFC 5246
+fn prf(secret: &[u8], label: &[u8], seed: &[u8], length: usize) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+    let label_seed = [label, seed].concat();
+    let mut a = hmac::sign(&key, &label_seed).as_ref().to_vec();
+    let mut output = Vec::with_capacity(length + 32);
+    while output.len() < length {
+        let mut context = hmac::Context::with_key(&key);
+        context.update(&a);
+        context.update(&label_seed);
+        output.extend_from_slice(context.sign().as_ref());
+        a = hmac::sign(&key, &a).as_ref().to_vec();
+    }
+    output.truncate(length);
+    output
+}
+
+/// Encodes a length in three bytes, big endian
+fn u24(value: usize) -> [u8; 3] {
+    let bytes = (value as u32).to_be_bytes();
+    [bytes[1], bytes[2], bytes[3]]
+}
+
+/// Decodes a length from three bytes, big endian
+fn from_u24(bytes: &[u8]) -> usize {
+    usize::from(bytes[0]) << 16 | usize::from(bytes[1]) << 8 | usize::from(bytes[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const PSK: &str = "0123456789abcdef0123456789abcdef";
+
+    fn hex(digits: &str) -> Vec<u8> {
+        (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn randoms() -> ([u8; 32], [u8; 32]) {
+        let mut client_random = [0; 32];
+        let mut server_random = [0; 32];
+        for i in 0..32 {
+            client_random[i] = i as u8;
+            server_random[i] = 32 + i as u8;
+        }
+        (client_random, server_random)
+    }
+
+    #[test]
+    fn prf_matches_known_answer() {
+        let output = prf(
+            &hex("9bbe436ba940f017b17652849a71db35"),
+            b"test label",
+            &hex("a0ba9f936cda311827a6f796ffd5198c"),
+            100,
+        );
+        let expected = hex(concat!(
+            "e3f229ba727be17b8d122620557cd453c2aab21d07c3d495329b52d4e61edb5a",
+            "6b301791e90d35c9c9a46b4e14baf9af0fa022f7077def17abfd3797c0564bab",
+            "4fbc91666e9def9b97fce34f796789baa48082d122ee42c5a72e5a5110fff701",
+            "87347b66",
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn keys_are_expanded_from_psk() {
+        let (client_random, server_random) = randoms();
+        let master_secret = master_secret(&hex(PSK), &client_random, &server_random);
+        let expected = hex(concat!(
+            "ff5cdc0a199dcb2c2407455f18c12c57a68a0e9e991ce0bc8a2bd5ad985e5831",
+            "ae477178d1c696c73f97ffabc248d32d",
+        ));
+        assert_eq!(master_secret, expected);
+        let expected = hex("7fa6385848753c22f113967b97aa7cc0b6160845bb81adb3cd3c2fc7079e356c971dab3e461dd3dc");
+        assert_eq!(key_block(&master_secret, &client_random, &server_random), expected);
+    }
+
+    #[test]
+    fn records_are_sealed_with_aes_gcm() {
+        let key_block = hex("7fa6385848753c22f113967b97aa7cc0b6160845bb81adb3cd3c2fc7079e356c971dab3e461dd3dc");
+        let keys = RecordKeys::new(&key_block[..16], &key_block[32..36]);
+        let record_number = 1 << 48 | 5;
+        let fragment = keys.seal(APPLICATION_DATA, record_number, b"HueStream");
+        assert_eq!(fragment, hex("00010000000000053cb409fceba9a3ee42f0e996c3a062bbfaa48c6b2f3e0ffd6f"));
+        assert_eq!(keys.open(APPLICATION_DATA, record_number, &fragment), Some(b"HueStream".to_vec()));
+        // Records are authenticated with their number and type
+        assert_eq!(keys.open(APPLICATION_DATA, record_number + 1, &fragment), None);
+        assert_eq!(keys.open(HANDSHAKE, record_number, &fragment), None);
+    }
+
+    /// Two sockets connected to each other
+    fn socket_pair() -> (UdpSocket, UdpSocket) {
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(server.local_addr().unwrap()).unwrap();
+        server.connect(client.local_addr().unwrap()).unwrap();
+        (client, server)
+    }
+
+    /// Waits for the next handshake message from the client.
+    ///
+    /// The server side reuses the record layer of the client, with the keys swapped.
+    fn next_message(server: &mut DtlsClient, buffer: &mut [u8]) -> Option<Message> {
+        loop {
+            let size = server.socket.recv(buffer).ok()?;
+            if let Some(message) = server.receive(&buffer[..size]).unwrap().pop() {
+                return Some(message);
+            }
+        }
+    }
+
+    /// Sends a datagram of `records`.
+    fn send_flight(server: &mut DtlsClient, records: &[Outgoing]) {
+        let datagram: Vec<u8> = records.iter().flat_map(|outgoing| server.record(outgoing)).collect();
+        server.socket.send(&datagram).unwrap();
+    }
+
+    /// Serves one handshake with `psk`, asking for a cookie first.
+    ///
+    /// Before its Finished message, the server sends records which the
+    /// client must drop. Returns the first application data received.
+    fn serve(socket: UdpSocket, psk: &[u8]) -> Option<Vec<u8>> {
+        socket.set_read_timeout(Some(RETRANSMIT_TIMEOUT * 2)).unwrap();
+        let mut server = DtlsClient::new(socket);
+        let mut buffer = vec![0; MAX_DATAGRAM_SIZE];
+        next_message(&mut server, &mut buffer)?;
+        let cookie = [1, 2, 3, 4];
+        let request = server.handshake_message(HELLO_VERIFY_REQUEST, &[&VERSION[..], &[4], &cookie].concat());
+        send_flight(&mut server, &[request]);
+        server.transcript.clear();
+        let hello = next_message(&mut server, &mut buffer)?;
+        assert_eq!(hello.msg_type, CLIENT_HELLO);
+        assert_eq!(hello_verify_cookie(&hello.body[33..]).unwrap(), cookie);
+
+        let mut client_random = [0; 32];
+        client_random.copy_from_slice(&hello.body[2..34]);
+        let server_random = [7; 32];
+        let hello = [&VERSION[..], &server_random, &[0], &CIPHER_SUITE, &[0]].concat();
+        let flight = [
+            server.handshake_message(SERVER_HELLO, &hello),
+            server.handshake_message(SERVER_HELLO_DONE, &[]),
+        ];
+        let master_secret = master_secret(psk, &client_random, &server_random);
+        let key_block = key_block(&master_secret, &client_random, &server_random);
+        server.client_keys = Some(RecordKeys::new(&key_block[16..32], &key_block[36..40]));
+        server.server_keys = Some(RecordKeys::new(&key_block[..16], &key_block[32..36]));
+        // Answer each flight of the client, until its Finished message could be decrypted
+        let finished = loop {
+            send_flight(&mut server, &flight);
+            let size = server.socket.recv(&mut buffer).ok()?;
+            let messages = server.receive(&buffer[..size]).unwrap();
+            if let Some(finished) = messages.into_iter().find(|message| message.msg_type == FINISHED) {
+                break finished;
+            }
+        };
+        let expected = prf(&master_secret, b"client finished", &server.transcript_hash_without(&finished), VERIFY_DATA_LEN);
+        assert_eq!(finished.body, expected);
+
+        // A garbage datagram, a fragmented message and a record which can't be
+        // decrypted, none of which counts as sent by the server
+        server.socket.send(&[0xff; 5]).unwrap();
+        let mut fragmented = server.handshake_message(FINISHED, &[0; VERIFY_DATA_LEN]);
+        fragmented.payload[8] = 1;
+        server.message_sequence -= 1;
+        server.transcript.truncate(server.transcript.len() - HANDSHAKE_HEADER_LEN - VERIFY_DATA_LEN);
+        send_flight(&mut server, &[fragmented]);
+        let mut forged = server.record(&Outgoing {
+            content_type: HANDSHAKE,
+            epoch: 1,
+            payload: vec![0; 24],
+        });
+        forged[RECORD_HEADER_LEN + EXPLICIT_NONCE_LEN] ^= 1;
+        server.record_sequences[1] -= 1;
+        server.socket.send(&forged).unwrap();
+
+        let verify_data = server.verify_data(&master_secret, b"server finished");
+        let mut finished = server.handshake_message(FINISHED, &verify_data);
+        finished.epoch = 1;
+        let change_cipher_spec = Outgoing {
+            content_type: CHANGE_CIPHER_SPEC,
+            epoch: 0,
+            payload: vec![1],
+        };
+        send_flight(&mut server, &[change_cipher_spec, finished]);
+
+        let size = server.socket.recv(&mut buffer).ok()?;
+        let record = &buffer[..size];
+        assert_eq!(record[0], APPLICATION_DATA);
+        let record_number = u64::from_be_bytes([record[3], record[4], record[5], record[6], record[7], record[8], record[9], record[10]]);
+        server.server_keys.as_ref()?.open(APPLICATION_DATA, record_number, &record[RECORD_HEADER_LEN..])
+    }
+
+    #[test]
+    fn handshake_completes_despite_invalid_records() {
+        let (client, server) = socket_pair();
+        let server = thread::spawn(move || serve(server, &hex(PSK)));
+        let mut client = DtlsClient::connect(client, b"user", &hex(PSK)).unwrap();
+        client.send(b"HueStream").unwrap();
+        assert_eq!(server.join().unwrap(), Some(b"HueStream".to_vec()));
+    }
+
+    #[test]
+    fn wrong_psk_is_rejected() {
+        let (client, server) = socket_pair();
+        let server = thread::spawn(move || serve(server, &hex("00112233445566778899aabbccddeeff")));
+        match DtlsClient::connect(client, b"user", &hex(PSK)) {
+            Err(Error::ClientKeyRejected) => {}
+            Err(e) => panic!("Unexpected error {}", e),
+            Ok(_) => panic!("Connected with the wrong key"),
+        }
+        assert_eq!(server.join().unwrap(), None);
+    }
+}
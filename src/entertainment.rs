@@ -0,0 +1,134 @@
+use crate::bridge::{Bridge, Credentials};
+use crate::dtls::DtlsClient;
+use crate::error::{Error, Result};
+use std::net::{ToSocketAddrs, UdpSocket};
+
+/// UDP port bridges receive streamed colors on
+const STREAMING_PORT: u16 = 2100;
+
+/// Maximum number of channels a single message may set
+pub const MAX_CHANNELS: usize = 20;
+
+/// Length of the UUID of an entertainment area
+const AREA_ID_LEN: usize = 36;
+
+/// A running stream of colors to the lights of an entertainment area
+///
+/// Created by [`crate::Bridge::start_streaming`]. While the stream runs,
+/// the bridge ignores other changes to the lights of the area. It stops
+/// the stream if no colors arrive for ten seconds, so colors should be
+/// sent continuously, about 25 to 50 times per second. Dropping the
+/// session stops the stream, too.
+pub struct StreamSession {
+    bridge: Bridge,
+    credentials: Credentials,
+    area: String,
+    connection: DtlsClient,
+    /// Sequence number of the next message, which the bridge ignores
+    sequence: u8,
+    /// Tells wether the stream still has to be stopped
+    active: bool,
+}
+
+impl StreamSession {
+    /// Connects to the bridge, after streaming to `area` was activated.
+    pub(crate) fn connect(bridge: Bridge, credentials: Credentials, area: String) -> Result<StreamSession> {
+        if area.len() != AREA_ID_LEN {
+            return Err(Error::Arbitrary(format!("{} is not the UUID of an entertainment area", area)));
+        }
+        let psk = match credentials.clientkey.as_deref().and_then(decode_hex) {
+            Some(psk) => psk,
+            None => {
+                return Err(Error::Arbitrary(String::from(
+                    "Streaming needs the clientkey of the user, see register_user_with_clientkey",
+                )))
+            }
+        };
+        let address = format!("{}:{}", host_of(&bridge.url_base), STREAMING_PORT)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::Arbitrary(format!("Can't resolve {}", bridge.url_base)))?;
+        let socket = UdpSocket::bind(if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" })?;
+        socket.connect(address)?;
+        let connection = DtlsClient::connect(socket, credentials.username.as_bytes(), &psk)?;
+        Ok(StreamSession {
+            bridge,
+            credentials,
+            area,
+            connection,
+            sequence: 0,
+            active: true,
+        })
+    }
+
+    /// Sets the colors of channels of the area.
+    ///
+    /// Each entry is the identifier of a channel and its color as 16 bit
+    /// red, green and blue values. At most [`MAX_CHANNELS`] can be set at
+    /// once.
+    pub fn send_colors(&mut self, colors: &[(u8, [u16; 3])]) -> Result<()> {
+        if colors.len() > MAX_CHANNELS {
+            return Err(Error::Arbitrary(format!(
+                "At most {} channels can be set at once, not {}",
+                MAX_CHANNELS,
+                colors.len()
+            )));
+        }
+        let message = color_message(&self.area, self.sequence, colors);
+        self.sequence = self.sequence.wrapping_add(1);
+        self.connection.send(&message)
+    }
+
+    /// Ends the stream, so the lights of the area can be controlled normally again.
+    pub fn stop(mut self) -> Result<()> {
+        self.active = false;
+        // The bridge ends the stream anyway once it is deactivated
+        let _ = self.connection.close();
+        self.bridge.set_streaming(&self.credentials, &self.area, false)
+    }
+}
+
+impl Drop for StreamSession {
+    fn drop(&mut self) {
+        if self.active {
+            let _ = self.connection.close();
+            let _ = self.bridge.set_streaming(&self.credentials, &self.area, false);
+        }
+    }
+}
+
+/// A message of the Entertainment API which sets RGB `colors`
+fn color_message(area: &str, sequence: u8, colors: &[(u8, [u16; 3])]) -> Vec<u8> {
+    let mut message = b"HueStream".to_vec();
+    // Version 2.0, the sequence number, two reserved bytes, the RGB color space and another reserved byte
+    message.extend_from_slice(&[2, 0, sequence, 0, 0, 0, 0]);
+    message.extend_from_slice(area.as_bytes());
+    for (channel, rgb) in colors {
+        message.push(*channel);
+        for value in rgb {
+            message.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+    message
+}
+
+/// The host of a URL like `http://192.168.1.5:80/`, including the brackets of IPv6 addresses
+fn host_of(url: &str) -> &str {
+    let authority = url.split("://").nth(1).unwrap_or(url);
+    let authority = authority.split('/').next().unwrap_or(authority);
+    match authority.find(']') {
+        Some(end) => &authority[..=end],
+        None => authority.split(':').next().unwrap_or(authority),
+    }
+}
+
+/// Decodes a key given as hex digits, like the `clientkey`
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -1,9 +1,107 @@
+use std::fmt;
+
 #[derive(Deserialize, Debug)]
+/// An error reported by the bridge
 pub struct ApiError {
     #[serde(rename = "type")]
-    error_type: u16,
-    address: String,
-    description: String,
+    /// The numeric error code
+    pub error_type: u16,
+    /// The resource the error refers to
+    pub address: String,
+    /// A human-readable description of the error
+    pub description: String,
+}
+
+impl ApiError {
+    /// The kind of error, derived from the numeric error code
+    pub fn kind(&self) -> ApiErrorKind {
+        ApiErrorKind::from(self.error_type)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Kinds of errors reported by the bridge
+pub enum ApiErrorKind {
+    /// The user name is unknown to the bridge (1)
+    UnauthorizedUser,
+    /// The request body isn't valid JSON (2)
+    InvalidJson,
+    /// The addressed resource doesn't exist (3)
+    ResourceNotAvailable,
+    /// The HTTP method isn't supported for the resource (4)
+    MethodNotAvailable,
+    /// The request body lacks required parameters (5)
+    MissingParameters,
+    /// The resource has no such parameter (6)
+    ParameterNotAvailable,
+    /// A parameter has an invalid value (7)
+    InvalidValue,
+    /// The parameter can't be changed (8)
+    ParameterNotModifiable,
+    /// A list in the request body has too many items (11)
+    TooManyItems,
+    /// The bridge needs a connection to the Hue portal (12)
+    PortalConnectionRequired,
+    /// The link button of the bridge wasn't pressed (101)
+    LinkButtonNotPressed,
+    /// The device is turned off, so the parameter can't be changed (201)
+    DeviceOff,
+    /// The bridge can't store any more groups (301)
+    GroupTableFull,
+    /// The bridge can't store any more scenes (402)
+    SceneBufferFull,
+    /// The bridge can't store any more sensors (502)
+    SensorListFull,
+    /// The bridge can't store any more rules (601)
+    RuleEngineFull,
+    /// The bridge can't store any more schedules (701)
+    ScheduleListFull,
+    /// The bridge failed internally (901)
+    InternalError,
+    /// Any other error code
+    Other(u16),
+}
+
+impl ApiErrorKind {
+    /// Tells wether the bridge ran out of space for a kind of resource
+    /// 
+    /// `Bridge::get_capabilities` tells how much space is left for each kind.
+    pub fn is_capacity_exceeded(&self) -> bool {
+        matches!(
+            self,
+            ApiErrorKind::GroupTableFull
+                | ApiErrorKind::SceneBufferFull
+                | ApiErrorKind::SensorListFull
+                | ApiErrorKind::RuleEngineFull
+                | ApiErrorKind::ScheduleListFull
+        )
+    }
+}
+
+impl From<u16> for ApiErrorKind {
+    fn from(code: u16) -> ApiErrorKind {
+        match code {
+            1 => ApiErrorKind::UnauthorizedUser,
+            2 => ApiErrorKind::InvalidJson,
+            3 => ApiErrorKind::ResourceNotAvailable,
+            4 => ApiErrorKind::MethodNotAvailable,
+            5 => ApiErrorKind::MissingParameters,
+            6 => ApiErrorKind::ParameterNotAvailable,
+            7 => ApiErrorKind::InvalidValue,
+            8 => ApiErrorKind::ParameterNotModifiable,
+            11 => ApiErrorKind::TooManyItems,
+            12 => ApiErrorKind::PortalConnectionRequired,
+            101 => ApiErrorKind::LinkButtonNotPressed,
+            201 => ApiErrorKind::DeviceOff,
+            301 => ApiErrorKind::GroupTableFull,
+            402 => ApiErrorKind::SceneBufferFull,
+            502 => ApiErrorKind::SensorListFull,
+            601 => ApiErrorKind::RuleEngineFull,
+            701 => ApiErrorKind::ScheduleListFull,
+            901 => ApiErrorKind::InternalError,
+            code => ApiErrorKind::Other(code),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -12,11 +110,79 @@ pub enum Error {
     Reqwest(reqwest::Error),
     Xml(serde_xml_rs::Error),
     ApiErrors(Vec<ApiError>),
+    /// The bridge answered with an HTTP error status
+    Http { status: u16, body: String },
 	Json(serde_json::Error),
     NoBridgeFound,
+    /// An operation didn't succeed within the given time
+    Timeout,
+    /// The light with the given identifier isn't reachable by the bridge, e.g. because it's powered off
+    Unreachable(String),
+    /// The bridge didn't accept the `clientkey` of the user when streaming was started
+    ClientKeyRejected,
     Arbitrary(String),
 }
 
+impl Error {
+    /// The kinds of the errors reported by the bridge, if it reported any
+    pub fn api_error_kinds(&self) -> Vec<ApiErrorKind> {
+        match self {
+            Error::ApiErrors(errors) => errors.iter().map(ApiError::kind).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Tells wether the bridge refused a registration because its link button wasn't pressed.
+    /// 
+    /// This isn't a failure, but a request to the user: Once the button is
+    /// pressed, the registration can be tried again within 30 seconds.
+    pub fn is_link_button_not_pressed(&self) -> bool {
+        self.api_error_kinds().contains(&ApiErrorKind::LinkButtonNotPressed)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (error {} at {})", self.description, self.error_type, self.address)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Reqwest(e) => write!(f, "HTTP request failed: {}", e),
+            Error::Xml(e) => write!(f, "Invalid XML from bridge: {}", e),
+            Error::ApiErrors(errors) => {
+                write!(f, "Bridge reported errors")?;
+                for (i, error) in errors.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { ";" }, error)?;
+                }
+                Ok(())
+            }
+            Error::Http { status, body } => write!(f, "Bridge answered with HTTP status {}: {}", status, body),
+            Error::Json(e) => write!(f, "Invalid JSON from bridge: {}", e),
+            Error::NoBridgeFound => write!(f, "No bridge found"),
+            Error::Timeout => write!(f, "Timed out"),
+            Error::Unreachable(light) => write!(f, "Light {} is unreachable", light),
+            Error::ClientKeyRejected => write!(f, "The bridge rejected the clientkey"),
+            Error::Arbitrary(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Reqwest(e) => Some(e),
+            Error::Xml(e) => Some(e),
+            Error::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(error: std::io::Error) -> Error {
         Error::Io(error)
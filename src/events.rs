@@ -0,0 +1,148 @@
+use crate::error::Result;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+
+#[derive(Deserialize, Serialize, Debug)]
+/// A change pushed by the bridge via the event stream of the API v2
+pub struct Event {
+    /// Unique identifier of the event
+    pub id: String,
+    /// Time the event was created, like `2021-11-08T11:16:54Z`
+    pub creationtime: String,
+    #[serde(rename = "type")]
+    pub event_type: EventType,
+    /// The resources the event is about
+    #[serde(default)]
+    pub data: Vec<EventData>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
+#[serde(from = "String", into = "String")]
+/// What happened to the resources of an event
+pub enum EventType {
+    Add,
+    Update,
+    Delete,
+    Error,
+    /// Any type not known to this crate
+    Other(String),
+}
+
+impl From<String> for EventType {
+    fn from(event_type: String) -> EventType {
+        match event_type.as_str() {
+            "add" => EventType::Add,
+            "update" => EventType::Update,
+            "delete" => EventType::Delete,
+            "error" => EventType::Error,
+            _ => EventType::Other(event_type),
+        }
+    }
+}
+
+impl From<EventType> for String {
+    fn from(event_type: EventType) -> String {
+        match event_type {
+            EventType::Add => String::from("add"),
+            EventType::Update => String::from("update"),
+            EventType::Delete => String::from("delete"),
+            EventType::Error => String::from("error"),
+            EventType::Other(event_type) => event_type,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+/// A resource changed by an event, e.g. a light or a button
+pub struct EventData {
+    /// The API v2 identifier of the resource
+    pub id: String,
+    /// The address of the resource in the API v1, like `/lights/1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_v1: Option<String>,
+    /// Like `light`, `button` or `motion`
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    /// The changed attributes, e.g. `on` or `dimming` for a light
+    #[serde(flatten)]
+    pub attributes: HashMap<String, serde_json::Value>,
+}
+
+/// An iterator over the events pushed by a bridge
+///
+/// Created by [`crate::Bridge::event_stream`]. The iteration ends when
+/// the bridge closes the connection.
+pub struct EventStream<R> {
+    reader: BufReader<R>,
+    /// Events received, but not yet yielded
+    pending: VecDeque<Event>,
+}
+
+impl<R: Read> EventStream<R> {
+    /// Reads Server-Sent Events from `reader`.
+    ///
+    /// Example:
+    /// ```
+    /// use hust::events::{EventStream, EventType};
+    ///
+    /// let stream = b": hi\n\nid: 1:0\ndata: [{\"creationtime\":\"2021-11-08T11:16:54Z\",\"id\":\"a1\",\"type\":\"update\",\"data\":[{\"id\":\"b2\",\"id_v1\":\"/lights/1\",\"on\":{\"on\":true},\"type\":\"light\"}]}]\n\n";
+    /// let events: Vec<_> = EventStream::new(&stream[..]).collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(events[0].event_type, EventType::Update);
+    /// assert_eq!(events[0].data[0].id_v1.as_deref(), Some("/lights/1"));
+    /// ```
+    pub fn new(reader: R) -> Self {
+        EventStream {
+            reader: BufReader::new(reader),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Reads the data of the next message, or `None` at the end of the stream.
+    ///
+    /// Messages are separated by empty lines. Their data may be split onto
+    /// several `data:` lines, while other fields like `id:` are ignored.
+    fn next_message(&mut self) -> Option<Result<String>> {
+        let mut data = String::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+            let line = line.trim_end_matches(&['\r', '\n'][..]);
+            if line.is_empty() {
+                if !data.is_empty() {
+                    return Some(Ok(data));
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if !data.is_empty() {
+                    data.push('\n');
+                }
+                data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for EventStream<R> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Result<Event>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            let data = match self.next_message()? {
+                Ok(data) => data,
+                Err(e) => return Some(Err(e)),
+            };
+            // Each message contains a list of events
+            match serde_json::from_str::<Vec<Event>>(&data) {
+                Ok(events) => self.pending.extend(events),
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use crate::lights::{Alert, Effect};
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Attributes of a group of lights
+pub struct Group {
+    pub name: String,
+    /// Identifiers of the lights in this group
+    pub lights: Vec<String>,
+    /// Like `LightGroup` or `Room`
+    #[serde(rename = "type")]
+    pub group_type: String,
+    /// The kind of room, only present for rooms
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<RoomClass>,
+    pub state: GroupState,
+    /// The state last applied to all lights of the group
+    pub action: GroupAction,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Summarized state of the lights in a group
+pub struct GroupState {
+    /// Tells wether all lights of the group are turned on
+    pub all_on: bool,
+    /// Tells wether any light of the group is turned on
+    pub any_on: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// The state last applied to the lights of a group
+///
+/// Most attributes are only present if a light of the group supports them.
+pub struct GroupAction {
+    pub on: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bri: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hue: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sat: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xy: Option<[f32; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ct: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert: Option<Alert>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effect: Option<Effect>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colormode: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+/// The kind of a room, which the Hue app picks an icon by
+pub enum RoomClass {
+    LivingRoom,
+    Kitchen,
+    Dining,
+    Bedroom,
+    KidsBedroom,
+    Bathroom,
+    Nursery,
+    Recreation,
+    Office,
+    Gym,
+    Hallway,
+    Toilet,
+    FrontDoor,
+    Garage,
+    Terrace,
+    Garden,
+    Driveway,
+    Carport,
+    Home,
+    Downstairs,
+    Upstairs,
+    TopFloor,
+    Attic,
+    GuestRoom,
+    Staircase,
+    Lounge,
+    ManCave,
+    Computer,
+    Studio,
+    Music,
+    Tv,
+    Reading,
+    Closet,
+    Storage,
+    LaundryRoom,
+    Balcony,
+    Porch,
+    Barbecue,
+    Pool,
+    Free,
+    Other,
+    /// Any class not known to this crate
+    Unknown(String),
+}
+
+impl From<String> for RoomClass {
+    fn from(class: String) -> RoomClass {
+        match class.as_str() {
+            "Living room" => RoomClass::LivingRoom,
+            "Kitchen" => RoomClass::Kitchen,
+            "Dining" => RoomClass::Dining,
+            "Bedroom" => RoomClass::Bedroom,
+            "Kids bedroom" => RoomClass::KidsBedroom,
+            "Bathroom" => RoomClass::Bathroom,
+            "Nursery" => RoomClass::Nursery,
+            "Recreation" => RoomClass::Recreation,
+            "Office" => RoomClass::Office,
+            "Gym" => RoomClass::Gym,
+            "Hallway" => RoomClass::Hallway,
+            "Toilet" => RoomClass::Toilet,
+            "Front door" => RoomClass::FrontDoor,
+            "Garage" => RoomClass::Garage,
+            "Terrace" => RoomClass::Terrace,
+            "Garden" => RoomClass::Garden,
+            "Driveway" => RoomClass::Driveway,
+            "Carport" => RoomClass::Carport,
+            "Home" => RoomClass::Home,
+            "Downstairs" => RoomClass::Downstairs,
+            "Upstairs" => RoomClass::Upstairs,
+            "Top floor" => RoomClass::TopFloor,
+            "Attic" => RoomClass::Attic,
+            "Guest room" => RoomClass::GuestRoom,
+            "Staircase" => RoomClass::Staircase,
+            "Lounge" => RoomClass::Lounge,
+            "Man cave" => RoomClass::ManCave,
+            "Computer" => RoomClass::Computer,
+            "Studio" => RoomClass::Studio,
+            "Music" => RoomClass::Music,
+            "TV" => RoomClass::Tv,
+            "Reading" => RoomClass::Reading,
+            "Closet" => RoomClass::Closet,
+            "Storage" => RoomClass::Storage,
+            "Laundry room" => RoomClass::LaundryRoom,
+            "Balcony" => RoomClass::Balcony,
+            "Porch" => RoomClass::Porch,
+            "Barbecue" => RoomClass::Barbecue,
+            "Pool" => RoomClass::Pool,
+            "Free" => RoomClass::Free,
+            "Other" => RoomClass::Other,
+            _ => RoomClass::Unknown(class),
+        }
+    }
+}
+
+impl From<RoomClass> for String {
+    fn from(class: RoomClass) -> String {
+        let class = match class {
+            RoomClass::LivingRoom => "Living room",
+            RoomClass::Kitchen => "Kitchen",
+            RoomClass::Dining => "Dining",
+            RoomClass::Bedroom => "Bedroom",
+            RoomClass::KidsBedroom => "Kids bedroom",
+            RoomClass::Bathroom => "Bathroom",
+            RoomClass::Nursery => "Nursery",
+            RoomClass::Recreation => "Recreation",
+            RoomClass::Office => "Office",
+            RoomClass::Gym => "Gym",
+            RoomClass::Hallway => "Hallway",
+            RoomClass::Toilet => "Toilet",
+            RoomClass::FrontDoor => "Front door",
+            RoomClass::Garage => "Garage",
+            RoomClass::Terrace => "Terrace",
+            RoomClass::Garden => "Garden",
+            RoomClass::Driveway => "Driveway",
+            RoomClass::Carport => "Carport",
+            RoomClass::Home => "Home",
+            RoomClass::Downstairs => "Downstairs",
+            RoomClass::Upstairs => "Upstairs",
+            RoomClass::TopFloor => "Top floor",
+            RoomClass::Attic => "Attic",
+            RoomClass::GuestRoom => "Guest room",
+            RoomClass::Staircase => "Staircase",
+            RoomClass::Lounge => "Lounge",
+            RoomClass::ManCave => "Man cave",
+            RoomClass::Computer => "Computer",
+            RoomClass::Studio => "Studio",
+            RoomClass::Music => "Music",
+            RoomClass::Tv => "TV",
+            RoomClass::Reading => "Reading",
+            RoomClass::Closet => "Closet",
+            RoomClass::Storage => "Storage",
+            RoomClass::LaundryRoom => "Laundry room",
+            RoomClass::Balcony => "Balcony",
+            RoomClass::Porch => "Porch",
+            RoomClass::Barbecue => "Barbecue",
+            RoomClass::Pool => "Pool",
+            RoomClass::Free => "Free",
+            RoomClass::Other => "Other",
+            RoomClass::Unknown(class) => return class,
+        };
+        String::from(class)
+    }
+}
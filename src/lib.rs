@@ -1,13 +1,36 @@
 #[macro_use]
 extern crate serde_derive;
 extern crate reqwest;
+#[cfg(feature = "entertainment")]
+extern crate ring;
 extern crate serde;
 extern crate serde_json;
 extern crate serde_xml_rs;
+extern crate socket2;
+extern crate xml;
+pub mod color;
+pub mod config;
 pub mod error;
+pub mod events;
 pub use error::{Error, Result};
 pub mod lights;
+mod rate_limit;
+pub mod groups;
+pub mod resourcelinks;
+pub mod rules;
+pub mod scenes;
+pub mod schedules;
+pub mod sensors;
 pub mod bridge;
+pub mod transport;
+#[cfg(feature = "v2")]
+pub mod v2;
+#[cfg(feature = "entertainment")]
+mod dtls;
+#[cfg(feature = "entertainment")]
+pub mod entertainment;
 pub use bridge::Bridge;
 mod discovery;
-pub use discovery::find_bridges;
+pub use discovery::{find_bridges, find_bridges_channel, find_bridges_cloud, find_bridges_on, find_bridges_with_family, BridgeFinder, BridgeIds, DiscoveredBridge, DiscoveredBridges, DiscoveryOptions, IpFamily};
+#[cfg(feature = "async")]
+pub use discovery::find_bridges_async;
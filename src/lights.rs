@@ -1,4 +1,11 @@
-#[derive(Deserialize, Serialize, Debug, Default)]
+use crate::bridge::Bridge;
+use crate::color::{self, Color, Gamut};
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 /// Attributes of a light
 pub struct Light {
 	pub uniqueid: String,
@@ -7,24 +14,663 @@ pub struct Light {
 	pub name: String,
 	pub modelid: String,
 	pub manufacturername: String,
+	/// Missing for many lights of other manufacturers
+	#[serde(default)]
 	pub productid: String,
 	pub state: LightState,
 	pub swversion: String,
+	/// Missing for many lights of other manufacturers
+	#[serde(default)]
 	pub swconfigid: String,
+	/// What the light is able to do, missing for older lights
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub capabilities: Option<LightCapabilities>,
+	/// Name of the product, like `"Hue color lamp"`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub productname: Option<String>,
+	/// Configuration of the light, missing for older lights
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub config: Option<LightConfig>,
+}
+
+impl Light {
+	/// Moves a color into the gamut of this light, if it is known.
+	pub fn clamp_xy(&self, xy: [f32; 2]) -> [f32; 2] {
+		match self.capabilities.as_ref().and_then(|c| c.control.colorgamut.as_ref()) {
+			Some(gamut) => color::clamp_to_gamut(xy, gamut),
+			None => xy,
+		}
+	}
+
+	/// Limits a color temperature in mireds to the range of this light, if it is known.
+	pub fn clamp_ct(&self, ct: u16) -> u16 {
+		match self.capabilities.as_ref().and_then(|c| c.control.ct.as_ref()) {
+			Some(range) => ct.clamp(range.min, range.max.max(range.min)),
+			None => ct,
+		}
+	}
+
+	/// Tells wether the light can show colors.
+	///
+	/// This is known from the capabilities of the light, or else from its
+	/// type: `"Extended color light"` and `"Color light"` support colors,
+	/// while e.g. `"Color temperature light"`, `"Dimmable light"` and
+	/// `"On/Off plug-in unit"` don't.
+	/// ```
+	/// use hust::lights::Light;
+	///
+	/// let light = Light { light_type: String::from("Color temperature light"), ..Light::default() };
+	/// assert!(!light.supports_color());
+	/// assert!(light.supports_color_temperature());
+	/// ```
+	pub fn supports_color(&self) -> bool {
+		match self.capabilities.as_ref() {
+			Some(capabilities) if capabilities.control.colorgamut.is_some() => true,
+			_ => matches!(self.light_type.to_lowercase().as_str(), "extended color light" | "color light"),
+		}
+	}
+
+	/// Tells wether the color temperature of the light can be set.
+	///
+	/// This is known from the capabilities of the light, or else from its
+	/// type: `"Extended color light"` and `"Color temperature light"`
+	/// support color temperatures.
+	pub fn supports_color_temperature(&self) -> bool {
+		match self.capabilities.as_ref() {
+			Some(capabilities) if capabilities.control.ct.is_some() => true,
+			_ => matches!(self.light_type.to_lowercase().as_str(), "extended color light" | "color temperature light"),
+		}
+	}
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// Configuration of a light
+pub struct LightConfig {
+	/// The kind of light, used to pick an icon, like `"sultanbulb"`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub archetype: Option<String>,
+	/// Either `"functional"`, `"decorative"` or `"mixed"`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub function: Option<String>,
+	/// Either `"omnidirectional"`, `"upwards"` or `"downwards"`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub direction: Option<String>,
+	/// What the light does when it is powered on
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub startup: Option<LightStartup>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// Power-on behavior of a light
+pub struct LightStartup {
+	/// The behavior, like `"safety"` or `"powerfail"`
+	pub mode: String,
+	/// Tells wether the behavior was applied to the light yet
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub configured: Option<bool>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+/// What a light does when it is powered on, e.g. after a power outage
+pub enum StartupBehavior {
+	/// Turn on at full brightness in warm white
+	Safety,
+	/// Restore the state from before the power outage
+	Powerfail,
+	/// Restore the state the light had when it was last turned on
+	LastOnState,
+	/// Turn on with the given settings
+	Custom {
+		customsettings: CustomStartupSettings,
+	},
+}
+
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+/// Settings a light is powered on with for [`StartupBehavior::Custom`]
+pub struct CustomStartupSettings {
+	/// Brightness, from 1 to 254
+	pub bri: u8,
+	/// Color temperature in mireds
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ct: Option<u16>,
+	/// Color as CIE xy coordinates
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub xy: Option<[f32; 2]>,
 }
 
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// Capabilities of a light
+pub struct LightCapabilities {
+	/// Tells wether the light is certified by Philips
+	#[serde(default)]
+	pub certified: bool,
+	#[serde(default)]
+	pub control: LightControl,
+	/// Support for the Entertainment API, missing if not supported
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub streaming: Option<StreamingCapabilities>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// Limits of the attributes of a light
+pub struct LightControl {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub mindimlevel: Option<u32>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub maxlumen: Option<u32>,
+	/// Either `"A"`, `"B"`, `"C"` or `"other"`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub colorgamuttype: Option<String>,
+	/// The colors the light is able to show, missing for white lights
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub colorgamut: Option<Gamut>,
+	/// The supported color temperatures in mireds
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub ct: Option<ColorTemperatureRange>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// A range of color temperatures in mireds
+pub struct ColorTemperatureRange {
+	pub min: u16,
+	pub max: u16,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// Support of a light for the Entertainment API
+pub struct StreamingCapabilities {
+	/// Tells wether the light can render streamed colors
+	pub renderer: bool,
+	/// Tells wether the light can forward streamed colors to other lights
+	pub proxy: bool,
+}
+
+/// Identifiers of lights that couldn't be parsed, with the reason
+pub type UnparseableLights = Vec<(String, Error)>;
+
 #[derive(Deserialize, Serialize, Debug, Default)]
+/// Lights found by the latest search for new lights
+pub struct NewLights {
+	/// Either `"active"` during a search, `"none"` if there was none
+	/// yet, or the time of the last search
+	#[serde(rename = "lastscan")]
+	pub last_scan: String,
+	/// The new lights, keyed by their identifier
+	#[serde(flatten)]
+	pub lights: HashMap<String, NewLight>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A light found by a search for new lights
+pub struct NewLight {
+	pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 /// Current state of a light
+///
+/// The default state is off with a brightness of 0, which no light is
+/// really in. Don't apply it to a light, but create states with
+/// [`LightState::new_on`] or [`LightState::new_off`], or read them from
+/// the bridge. Only the writable attributes of a state are sent to the
+/// bridge, see [`LightState::to_change`].
 pub struct LightState {
 	/// Tells wether the light is turned on
 	pub on: bool,
-	/// Brightness
+	/// Brightness, missing for lights that can only be switched
+	#[serde(default)]
 	pub bri: u8,
-	/// Color tone
-	pub ct: u16,
+	/// Color tone, missing for lights without color temperature
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub ct: Option<u16>,
+	/// Hue, from 0 to 65535
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub hue: Option<u16>,
+	/// Saturation, from 0 to 254
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub sat: Option<u8>,
+	/// Color as CIE xy coordinates
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub xy: Option<[f32; 2]>,
+	/// Dynamic effect
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub effect: Option<Effect>,
 	/// Alert mode
-	pub alert: String,
-	pub colormode: String,
-	pub mode: String,
+	#[serde(default)]
+	pub alert: Alert,
+	/// Either `"hs"`, `"xy"` or `"ct"`, missing for lights without color
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub colormode: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub mode: Option<String>,
 	pub reachable: bool,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+/// Alert mode of a light, used to identify it
+pub enum Alert {
+	/// No alert
+	#[default]
+	None,
+	/// A single blink
+	Select,
+	/// Breathing for 15 seconds
+	LSelect,
+	/// Any other mode reported by the bridge
+	Other(String),
+}
+
+impl From<String> for Alert {
+	fn from(alert: String) -> Alert {
+		match alert.as_str() {
+			"none" => Alert::None,
+			"select" => Alert::Select,
+			"lselect" => Alert::LSelect,
+			_ => Alert::Other(alert),
+		}
+	}
+}
+
+impl From<Alert> for String {
+	fn from(alert: Alert) -> String {
+		match alert {
+			Alert::None => String::from("none"),
+			Alert::Select => String::from("select"),
+			Alert::LSelect => String::from("lselect"),
+			Alert::Other(alert) => alert,
+		}
+	}
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+/// Dynamic effect of a light
+pub enum Effect {
+	/// No effect
+	#[default]
+	None,
+	/// Cycling through all hues
+	Colorloop,
+	/// Any other effect reported by the bridge
+	Other(String),
+}
+
+impl From<String> for Effect {
+	fn from(effect: String) -> Effect {
+		match effect.as_str() {
+			"none" => Effect::None,
+			"colorloop" => Effect::Colorloop,
+			_ => Effect::Other(effect),
+		}
+	}
+}
+
+impl From<Effect> for String {
+	fn from(effect: Effect) -> String {
+		match effect {
+			Effect::None => String::from("none"),
+			Effect::Colorloop => String::from("colorloop"),
+			Effect::Other(effect) => effect,
+		}
+	}
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+/// Which attributes of a light determine its color
+pub enum ColorMode {
+	/// `hue` and `sat`
+	Hs,
+	/// `xy`
+	Xy,
+	/// `ct`
+	Ct,
+	/// Any mode not known to this crate
+	Other(String),
+}
+
+impl From<String> for ColorMode {
+	fn from(mode: String) -> ColorMode {
+		match mode.as_str() {
+			"hs" => ColorMode::Hs,
+			"xy" => ColorMode::Xy,
+			"ct" => ColorMode::Ct,
+			_ => ColorMode::Other(mode),
+		}
+	}
+}
+
+impl From<ColorMode> for String {
+	fn from(mode: ColorMode) -> String {
+		match mode {
+			ColorMode::Hs => String::from("hs"),
+			ColorMode::Xy => String::from("xy"),
+			ColorMode::Ct => String::from("ct"),
+			ColorMode::Other(mode) => mode,
+		}
+	}
+}
+
+#[derive(Serialize, Debug, Default, Clone)]
+/// A change to the state of a light
+///
+/// Only the attributes that are set will be transmitted to the bridge,
+/// so several attributes can be changed at once in a single request.
+pub struct LightStateChange {
+	/// Turn the light on or off
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub on: Option<bool>,
+	/// Brightness, from 1 to 254
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub bri: Option<u8>,
+	/// Hue, from 0 to 65535
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub hue: Option<u16>,
+	/// Saturation, from 0 to 254
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub sat: Option<u8>,
+	/// Color as CIE xy coordinates
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub xy: Option<[f32; 2]>,
+	/// Color temperature in mireds
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub ct: Option<u16>,
+	/// Alert mode
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub alert: Option<Alert>,
+	/// Dynamic effect
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub effect: Option<Effect>,
+	/// Duration of the transition to the new state, in multiples of 100ms
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub transitiontime: Option<u16>,
+	/// Identifier of a scene to recall, only supported for groups
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub scene: Option<String>,
+}
+
+impl LightStateChange {
+	/// Creates a change that doesn't modify any attribute yet
+	///
+	/// Example:
+	/// ```
+	/// use hust::lights::LightStateChange;
+	///
+	/// let change = LightStateChange::new().on(true).bri(200).transition_time(10);
+	/// assert_eq!(change.bri, Some(200));
+	/// ```
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Turn the light on or off
+	pub fn on(mut self, on: bool) -> Self {
+		self.on = Some(on);
+		self
+	}
+
+	/// Set the brightness
+	pub fn bri(mut self, bri: u8) -> Self {
+		self.bri = Some(bri);
+		self
+	}
+
+	/// Set the hue
+	pub fn hue(mut self, hue: u16) -> Self {
+		self.hue = Some(hue);
+		self
+	}
+
+	/// Set the saturation
+	pub fn sat(mut self, sat: u8) -> Self {
+		self.sat = Some(sat);
+		self
+	}
+
+	/// Set the color as CIE xy coordinates
+	pub fn xy(mut self, xy: [f32; 2]) -> Self {
+		self.xy = Some(xy);
+		self
+	}
+
+	/// Set the color temperature in mireds
+	pub fn ct(mut self, ct: u16) -> Self {
+		self.ct = Some(ct);
+		self
+	}
+
+	/// Set the alert mode
+	pub fn alert(mut self, alert: Alert) -> Self {
+		self.alert = Some(alert);
+		self
+	}
+
+	/// Set the dynamic effect
+	pub fn effect(mut self, effect: Effect) -> Self {
+		self.effect = Some(effect);
+		self
+	}
+
+	/// Set the transition time in multiples of 100ms
+	pub fn transition_time(mut self, transitiontime: u16) -> Self {
+		self.transitiontime = Some(transitiontime);
+		self
+	}
+
+	/// Recall a scene, which only groups support
+	///
+	/// Other attributes are applied on top of the scene:
+	/// ```
+	/// use hust::lights::LightStateChange;
+	///
+	/// let change = LightStateChange::new().scene("AB34EF5").bri(100);
+	/// assert_eq!(serde_json::to_string(&change).unwrap(), r#"{"bri":100,"scene":"AB34EF5"}"#);
+	/// ```
+	pub fn scene(mut self, scene: &str) -> Self {
+		self.scene = Some(String::from(scene));
+		self
+	}
+
+	/// Set the color
+	///
+	/// Only the attributes for `color` are set, e.g. `ct` for a color
+	/// temperature. RGB colors are converted to `xy`, but not moved into
+	/// the gamut of a light, since it isn't known here. Use
+	/// [`Light::clamp_xy`] or `Bridge::set_color` for that.
+	pub fn color(mut self, color: Color) -> Self {
+		match color {
+			Color::Temperature(ct) => self.ct = Some(ct),
+			Color::HueSat { hue, sat } => {
+				self.hue = Some(hue);
+				self.sat = Some(sat);
+			}
+			Color::Xy(xy) => self.xy = Some(xy),
+			Color::Rgb(r, g, b) => self.xy = Some(color::rgb_to_xy(r, g, b)),
+		}
+		self
+	}
+
+	/// Set the duration of the transition to the new state
+	///
+	/// The duration is rounded down to multiples of 100ms and capped at
+	/// the maximum of 65535 × 100ms the bridge supports.
+	pub fn transition(self, transition: Duration) -> Self {
+		let deciseconds = (transition.as_millis() / 100).min(u128::from(u16::MAX));
+		self.transition_time(deciseconds as u16)
+	}
+
+	/// Tells wether applying the change twice has the same effect as once
+	///
+	/// Alerts flash the light again and scenes overwrite changes made in
+	/// between, while all other attributes are set to absolute values.
+	pub(crate) fn is_repeatable(&self) -> bool {
+		self.alert.is_none() && self.scene.is_none()
+	}
+}
+
+impl LightState {
+	/// A state in which the light is on with the brightness `bri`
+	///
+	/// `bri` is clamped to the range of 1 to 254 the bridge accepts.
+	pub fn new_on(bri: u8) -> Self {
+		LightState {
+			on: true,
+			bri: bri.clamp(1, 254),
+			reachable: true,
+			..LightState::default()
+		}
+	}
+
+	/// A state in which the light is off
+	pub fn new_off() -> Self {
+		LightState {
+			on: false,
+			reachable: true,
+			..LightState::default()
+		}
+	}
+
+	/// The color temperature in Kelvin
+	///
+	/// The bridge reports `ct` in mireds. Returns `None` if the light
+	/// does not report a color temperature.
+	pub fn color_temperature_kelvin(&self) -> Option<u16> {
+		let ct = self.ct.filter(|&ct| ct != 0)?;
+		u16::try_from(1_000_000 / u32::from(ct)).ok()
+	}
+
+	/// The change which brings a light into this state
+	///
+	/// Only the attributes which can be written are copied, so read-only
+	/// ones like `reachable` or `colormode` are left out. Of the color
+	/// attributes, only the ones of the current `colormode` are copied, since
+	/// the bridge would otherwise prefer `xy` over the others. The alert
+	/// is left out, as it's only a temporary signal. If the light is off,
+	/// only that is copied, since the bridge doesn't accept changes of
+	/// other attributes of lights that are off.
+	///
+	/// Example:
+	/// ```
+	/// use hust::lights::LightState;
+	///
+	/// let state: LightState = serde_json::from_str(
+	///     r#"{"on": true, "bri": 100, "ct": 300, "xy": [0.4, 0.4], "colormode": "ct", "reachable": true}"#
+	/// ).unwrap();
+	/// let change = state.to_change();
+	/// assert_eq!(change.ct, Some(300));
+	/// assert_eq!(change.xy, None);
+	///
+	/// // The brightness 0 is never sent, since the bridge would reject it
+	/// let mut state = LightState::default();
+	/// state.on = true;
+	/// assert_eq!(state.to_change().bri, None);
+	/// assert_eq!(LightState::new_on(0).to_change().bri, Some(1));
+	/// ```
+	pub fn to_change(&self) -> LightStateChange {
+		let mut change = LightStateChange::new().on(self.on);
+		if !self.on {
+			return change;
+		}
+		// A brightness of 0 means the light doesn't report one
+		if self.bri > 0 {
+			change = change.bri(self.bri);
+		}
+		match self.color_mode() {
+			Some(ColorMode::Xy) => change.xy = self.xy,
+			Some(ColorMode::Ct) => change.ct = self.ct,
+			Some(ColorMode::Hs) => {
+				change.hue = self.hue;
+				change.sat = self.sat;
+			}
+			_ => {}
+		}
+		change.effect = self.effect.clone();
+		change
+	}
+
+	/// The color the light currently shows, according to its `colormode`
+	///
+	/// Returns `None` for lights without color or color temperature.
+	pub fn color(&self) -> Option<Color> {
+		match self.color_mode()? {
+			ColorMode::Ct => self.ct.map(Color::Temperature),
+			ColorMode::Hs => Some(Color::HueSat {
+				hue: self.hue?,
+				sat: self.sat?,
+			}),
+			ColorMode::Xy => self.xy.map(Color::Xy),
+			ColorMode::Other(_) => None,
+		}
+	}
+
+	/// The `colormode`, which tells which attributes determine the color
+	///
+	/// Returns `None` for lights without color or color temperature.
+	pub fn color_mode(&self) -> Option<ColorMode> {
+		self.colormode.clone().map(ColorMode::from)
+	}
+
+	/// The brightness in percent
+	///
+	/// Maps the range of 1 to 254 the bridge uses onto 0.0–100.0.
+	pub fn brightness_percent(&self) -> f32 {
+		f32::from(self.bri.saturating_sub(1)).min(253.0) / 253.0 * 100.0
+	}
+}
+
+/// A light of a bridge, for changing several attributes in one request
+///
+/// Created by [`Bridge::light`]. The changes are collected and only sent
+/// to the bridge by `apply`.
+///
+/// Example:
+/// ```no_run
+/// use hust::{Bridge, Result};
+///
+/// fn dim(bridge: &Bridge, user: &str) -> Result<()> {
+///     bridge.light(user, "1").on(true).brightness(50).color_temp(400).apply()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct LightHandle<'a> {
+	bridge: &'a Bridge,
+	user: &'a str,
+	id: &'a str,
+	change: LightStateChange,
+}
+
+impl<'a> LightHandle<'a> {
+	pub(crate) fn new(bridge: &'a Bridge, user: &'a str, id: &'a str) -> Self {
+		LightHandle {
+			bridge,
+			user,
+			id,
+			change: LightStateChange::new(),
+		}
+	}
+
+	/// Turn the light on or off
+	pub fn on(mut self, on: bool) -> Self {
+		self.change = self.change.on(on);
+		self
+	}
+
+	/// Set the brightness
+	pub fn brightness(mut self, bri: u8) -> Self {
+		self.change = self.change.bri(bri);
+		self
+	}
+
+	/// Set the color temperature in mireds
+	pub fn color_temp(mut self, ct: u16) -> Self {
+		self.change = self.change.ct(ct);
+		self
+	}
+
+	/// Send all collected changes to the bridge in a single request
+	pub fn apply(self) -> Result<()> {
+		self.bridge.modify_light_state(self.user, self.id, &self.change)
+	}
+}
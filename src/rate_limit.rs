@@ -0,0 +1,36 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A token bucket holding a single token, which spaces requests evenly
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// Time it takes to refill the token
+    interval: Duration,
+    /// When the token will be available for the next request
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Allows `per_second` requests per second, or any number if it is 0.
+    pub fn new(per_second: u32) -> Option<RateLimiter> {
+        if per_second == 0 {
+            return None;
+        }
+        Some(RateLimiter {
+            interval: Duration::from_secs(1) / per_second,
+            next: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Takes the token and returns how long to wait before sending the request.
+    ///
+    /// Requests waiting concurrently are queued, since each one takes the
+    /// token in advance.
+    pub fn reserve(&self) -> Duration {
+        let now = Instant::now();
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let at = (*next).max(now);
+        *next = at + self.interval;
+        at - now
+    }
+}
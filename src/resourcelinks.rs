@@ -0,0 +1,15 @@
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A link between resources which belong together, e.g. the scenes of a room
+pub struct ResourceLink {
+    pub name: String,
+    pub description: String,
+    /// Identifies the application's kind of link, chosen by the application that created it
+    pub classid: u16,
+    /// The user who created the link
+    pub owner: String,
+    /// Tells wether the bridge may delete the link when it runs out of space
+    #[serde(default)]
+    pub recycle: bool,
+    /// The addresses of the linked resources, like `/groups/1` or `/scenes/abc`
+    pub links: Vec<String>,
+}
@@ -0,0 +1,33 @@
+use crate::schedules::Command;
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// An automation rule, executing actions when its conditions are met
+pub struct Rule {
+    pub name: String,
+    /// The user who created the rule
+    pub owner: String,
+    /// Time the rule was created
+    pub created: String,
+    /// Time the rule was triggered the last time, or `"none"`
+    pub lasttriggered: String,
+    /// How often the rule was triggered
+    pub timestriggered: u32,
+    /// Either `"enabled"`, `"disabled"` or `"resourcedeleted"`
+    pub status: String,
+    /// All conditions have to be met for the rule to trigger
+    pub conditions: Vec<RuleCondition>,
+    /// The requests the bridge sends when the rule triggers
+    pub actions: Vec<Command>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A condition of a rule
+pub struct RuleCondition {
+    /// The attribute the condition refers to, like `/sensors/2/state/presence`
+    pub address: String,
+    /// How the attribute is checked, like `"eq"`, `"gt"` or `"dx"`
+    pub operator: String,
+    /// The value the attribute is compared to, if the operator needs one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
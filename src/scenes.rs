@@ -0,0 +1,13 @@
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Attributes of a scene, a stored state for a set of lights
+pub struct Scene {
+    pub name: String,
+    /// Identifiers of the lights in this scene
+    pub lights: Vec<String>,
+    /// The user who created the scene
+    pub owner: String,
+    /// Tells wether the bridge may delete the scene when it runs out of space
+    pub recycle: bool,
+    /// Time of the last change of the scene, if it was ever changed
+    pub lastupdated: Option<String>,
+}
@@ -0,0 +1,32 @@
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A timer or alarm, executing a command at a certain time
+pub struct Schedule {
+    pub name: String,
+    pub description: String,
+    /// The request the bridge sends when the schedule fires
+    pub command: Command,
+    /// When the schedule fires, in the local time of the bridge
+    ///
+    /// Like `2020-12-24T18:00:00` for once, `W124/T07:00:00` for
+    /// weekdays or `PT00:10:00` for a timer.
+    pub localtime: String,
+    /// Either `"enabled"` or `"disabled"`
+    pub status: String,
+    /// Time the schedule was created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// Tells wether the schedule is deleted after firing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autodelete: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A request the bridge sends to itself, e.g. for a schedule or rule
+pub struct Command {
+    /// The resource to request, like `/api/<user>/groups/0/action`
+    pub address: String,
+    /// The HTTP method, like `"PUT"`
+    pub method: String,
+    /// The JSON body of the request
+    pub body: serde_json::Value,
+}
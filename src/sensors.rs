@@ -0,0 +1,92 @@
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Attributes of a sensor, like a motion sensor or a switch
+pub struct Sensor {
+    pub name: String,
+    /// The kind of sensor, like `"ZLLPresence"` or `"ZLLTemperature"`
+    #[serde(rename = "type")]
+    pub sensor_type: String,
+    pub modelid: String,
+    pub manufacturername: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uniqueid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swversion: Option<String>,
+    pub state: SensorState,
+    pub config: SensorConfig,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Current readings of a sensor
+///
+/// Which attributes are present depends on the type of the sensor.
+pub struct SensorState {
+    /// Tells wether a motion sensor detects presence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence: Option<bool>,
+    /// Temperature in 0.01 °C
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<i32>,
+    /// Light level in 10000 × log10(lux) + 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lightlevel: Option<u32>,
+    /// Tells wether the light level is below the dark threshold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dark: Option<bool>,
+    /// Tells wether the light level is above the daylight threshold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daylight: Option<bool>,
+    /// Code of the last button event of a switch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buttonevent: Option<u32>,
+    /// Time of the last change of the state
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lastupdated: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Configuration of a sensor
+pub struct SensorConfig {
+    /// Tells wether the sensor is enabled
+    pub on: bool,
+    /// Tells wether the bridge can reach the sensor, missing for virtual sensors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reachable: Option<bool>,
+    /// Battery level in percent, missing for sensors without battery
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery: Option<u8>,
+    /// Sensitivity of a motion sensor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitivity: Option<u8>,
+}
+
+#[derive(Serialize, Debug, Default)]
+/// A change to the configuration of a sensor
+///
+/// Only the attributes that are set will be transmitted to the bridge.
+pub struct SensorConfigChange {
+    /// Enable or disable the sensor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<bool>,
+    /// Sensitivity of a motion sensor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sensitivity: Option<u8>,
+}
+
+impl SensorConfigChange {
+    /// Creates a change that doesn't modify any attribute yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable the sensor
+    pub fn on(mut self, on: bool) -> Self {
+        self.on = Some(on);
+        self
+    }
+
+    /// Set the sensitivity of a motion sensor
+    pub fn sensitivity(mut self, sensitivity: u8) -> Self {
+        self.sensitivity = Some(sensitivity);
+        self
+    }
+}
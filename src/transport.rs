@@ -0,0 +1,95 @@
+use crate::error::{Error, Result};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::CONTENT_TYPE;
+pub use reqwest::Method;
+use std::fmt::Debug;
+use std::io::Read;
+
+/// A way to send HTTP requests to a bridge
+///
+/// [`crate::Bridge`] sends all its API requests through a transport, which
+/// is a `reqwest` client by default. Implement this trait to send them
+/// elsewhere, e.g. to a fake bridge in tests.
+///
+/// Only [`Transport::request`] has to be implemented.
+pub trait Transport: Debug + Send + Sync {
+    /// Sends a request with an optional JSON body and returns the response body.
+    ///
+    /// Responses with an HTTP error status should result in `Error::Http`.
+    fn request(&self, method: Method, url: &str, body: Option<Vec<u8>>) -> Result<Vec<u8>>;
+
+    /// Sends a request with additional headers, like the one authenticating requests to the API v2.
+    ///
+    /// By default, the headers are ignored, which suits fakes that don't
+    /// check authentication. Transports reaching a real bridge must send them.
+    fn request_with_headers(&self, method: Method, url: &str, headers: &[(&str, &str)], body: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        let _ = headers;
+        self.request(method, url, body)
+    }
+
+    /// Sends a GET request with additional headers and returns the response body while it is received.
+    ///
+    /// This is needed for endless responses like the event stream. By
+    /// default, streams aren't supported and an error is returned.
+    fn stream(&self, url: &str, headers: &[(&str, &str)]) -> Result<Box<dyn Read + Send>> {
+        let _ = headers;
+        Err(Error::Arbitrary(format!("This transport can't stream {}", url)))
+    }
+
+    /// Sends a GET request.
+    fn get(&self, url: &str) -> Result<Vec<u8>> {
+        self.request(Method::GET, url, None)
+    }
+
+    /// Sends a POST request with a JSON body.
+    fn post(&self, url: &str, body: Vec<u8>) -> Result<Vec<u8>> {
+        self.request(Method::POST, url, Some(body))
+    }
+
+    /// Sends a PUT request with a JSON body.
+    fn put(&self, url: &str, body: Vec<u8>) -> Result<Vec<u8>> {
+        self.request(Method::PUT, url, Some(body))
+    }
+
+    /// Sends a DELETE request.
+    fn delete(&self, url: &str) -> Result<Vec<u8>> {
+        self.request(Method::DELETE, url, None)
+    }
+}
+
+impl Transport for Client {
+    fn request(&self, method: Method, url: &str, body: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        self.request_with_headers(method, url, &[], body)
+    }
+
+    fn request_with_headers(&self, method: Method, url: &str, headers: &[(&str, &str)], body: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        let mut request = Client::request(self, method, url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        if let Some(body) = body {
+            request = request.header(CONTENT_TYPE, "application/json").body(body);
+        }
+        Ok(checked_status(request.send()?)?.bytes()?.to_vec())
+    }
+
+    fn stream(&self, url: &str, headers: &[(&str, &str)]) -> Result<Box<dyn Read + Send>> {
+        let mut request = self.get(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        Ok(Box::new(checked_status(request.send()?)?))
+    }
+}
+
+/// Turns a response with an HTTP error status into `Error::Http`.
+fn checked_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(Error::Http {
+            status: status.as_u16(),
+            body: response.text().unwrap_or_default(),
+        });
+    }
+    Ok(response)
+}
@@ -0,0 +1,236 @@
+#[derive(Deserialize, Debug)]
+/// The body of every response of the API v2
+pub(crate) struct Response<T> {
+    #[serde(default)]
+    pub errors: Vec<ResponseError>,
+    #[serde(default = "Vec::new")]
+    pub data: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+/// An error reported by the API v2
+pub(crate) struct ResponseError {
+    pub description: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A light, as described by the API v2
+///
+/// The API v2 identifies resources by UUIDs instead of numbers. The
+/// address of the light in the API v1 is kept in `id_v1`.
+pub struct LightV2 {
+    /// The UUID of the light
+    pub id: String,
+    /// The address of the light in the API v1, like `/lights/1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_v1: Option<String>,
+    /// The device the light belongs to
+    pub owner: ResourceIdentifier,
+    pub metadata: LightMetadata,
+    pub on: OnState,
+    /// Missing for lights that can't be dimmed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimming: Option<Dimming>,
+    /// Missing for lights without adjustable white
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_temperature: Option<ColorTemperature>,
+    /// Missing for lights without color
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorV2>,
+    /// Only present for gradient lights
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gradient: Option<Gradient>,
+    /// Like `normal` or `streaming`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A reference to another resource
+pub struct ResourceIdentifier {
+    /// The UUID of the resource
+    pub rid: String,
+    /// The type of the resource, like `device`
+    pub rtype: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Human-readable properties of a light
+pub struct LightMetadata {
+    pub name: String,
+    /// The kind of lamp, like `sultan_bulb`
+    pub archetype: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Whether a light is on
+pub struct OnState {
+    pub on: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// The brightness of a light
+pub struct Dimming {
+    /// The brightness in percent
+    pub brightness: f32,
+    /// The lowest brightness the light supports, in percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_dim_level: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// The white color temperature of a light
+pub struct ColorTemperature {
+    /// The color temperature in mireds, `None` if the light shows a color instead
+    pub mirek: Option<u16>,
+    /// Tells wether `mirek` is the current temperature
+    pub mirek_valid: bool,
+    pub mirek_schema: MirekSchema,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// The range of color temperatures a light supports, in mireds
+pub struct MirekSchema {
+    pub mirek_minimum: u16,
+    pub mirek_maximum: u16,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// The color of a light
+pub struct ColorV2 {
+    pub xy: Xy,
+    /// The colors the light can show
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gamut: Option<GamutV2>,
+    /// Like `A`, `B`, `C` or `other`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gamut_type: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq)]
+/// A point in the CIE color space
+pub struct Xy {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl From<[f32; 2]> for Xy {
+    fn from([x, y]: [f32; 2]) -> Xy {
+        Xy { x, y }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// The corners of the color triangle a light can show
+pub struct GamutV2 {
+    pub red: Xy,
+    pub green: Xy,
+    pub blue: Xy,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// The colors along a gradient light, like a gradient lightstrip
+pub struct Gradient {
+    /// The colors, from the start to the end of the light
+    pub points: Vec<GradientPoint>,
+    /// The maximum number of points the light supports
+    #[serde(default)]
+    pub points_capable: u8,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A color of a gradient
+pub struct GradientPoint {
+    pub color: GradientColor,
+}
+
+impl From<[f32; 2]> for GradientPoint {
+    fn from(xy: [f32; 2]) -> GradientPoint {
+        GradientPoint {
+            color: GradientColor { xy: xy.into() },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// The color of a gradient point
+pub struct GradientColor {
+    pub xy: Xy,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// An entertainment area, whose lights colors can be streamed to
+///
+/// Example:
+/// ```
+/// use hust::v2::EntertainmentArea;
+///
+/// let area: EntertainmentArea = serde_json::from_str(r#"{
+///     "id": "1a8d99cc-967b-44f2-9202-43f976c0fa6b",
+///     "metadata": {"name": "TV"},
+///     "configuration_type": "screen",
+///     "status": "inactive",
+///     "channels": [{
+///         "channel_id": 0,
+///         "position": {"x": -0.5, "y": 0.8, "z": 0.0},
+///         "members": [{"service": {"rid": "b4f3e2a1-faa0-4a3a-9a5b-33c1d4e5f6a7", "rtype": "entertainment"}, "index": 0}]
+///     }]
+/// }"#).unwrap();
+/// assert_eq!(area.metadata.name, "TV");
+/// assert_eq!(area.channels[0].position.x, -0.5);
+/// ```
+pub struct EntertainmentArea {
+    /// The UUID of the area, needed to stream to it
+    pub id: String,
+    /// The address of the area in the API v1, like `/groups/200`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_v1: Option<String>,
+    pub metadata: EntertainmentMetadata,
+    /// Like `screen`, `monitor`, `music`, `3dspace` or `other`
+    pub configuration_type: String,
+    /// Either `active` while colors are streamed, or `inactive`
+    pub status: String,
+    /// The channels colors are streamed to, each one with its position
+    #[serde(default)]
+    pub channels: Vec<EntertainmentChannel>,
+    /// The lights of the area
+    #[serde(default)]
+    pub light_services: Vec<ResourceIdentifier>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// Human-readable properties of an entertainment area
+pub struct EntertainmentMetadata {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A part of an entertainment area which shows a single streamed color
+pub struct EntertainmentChannel {
+    /// The identifier colors are streamed to
+    pub channel_id: u8,
+    pub position: Position,
+    /// The lights, or segments of them, showing the color of the channel
+    #[serde(default)]
+    pub members: Vec<ChannelMember>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq)]
+/// The location of a channel in the room, with each coordinate from -1 to 1
+///
+/// `x` runs from left to right, `y` from the back to the front, e.g.
+/// from the TV to the couch, and `z` from the floor to the ceiling.
+pub struct Position {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+/// A light, or one segment of it, in a channel
+pub struct ChannelMember {
+    /// The entertainment service of the light
+    pub service: ResourceIdentifier,
+    /// The segment of the light, 0 for lights with a single segment
+    pub index: u8,
+}